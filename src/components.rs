@@ -7,14 +7,980 @@ pub struct Enemy {
     pub max_health: f32,
     pub speed: f32,
     pub path_progress: f32,
+    /// Where the enemy was on the path before its last step
+    pub last_progress: f32,
 }
 
+/// A tower's base stats and settings. What it actually fires with, after
+/// bonuses from nearby towers, lives in `EffectiveStats`.
 #[derive(Component)]
 pub struct Tower {
+    pub kind: TowerKind,
     pub damage: f32,
     pub range: f32,
     pub fire_rate: f32,
-    pub last_shot: f32,
+    pub targeting: TargetingMode,
+    /// Aim where the target will be when the bullet arrives rather than where it is now
+    pub lead_targeting: bool,
+    pub level: u32,
+}
+
+impl Tower {
+    pub const MAX_LEVEL: u32 = 3;
+
+    /// Raise the tower a level, improving its damage and range
+    pub fn upgrade(&mut self) {
+        if self.level >= Self::MAX_LEVEL {
+            return;
+        }
+
+        self.level += 1;
+        let base = self.kind.tower();
+        self.damage = base.damage * level_scale(self.level, 0.25);
+        self.range = self.kind.range_at(self.level);
+    }
+}
+
+/// Multiplier for a stat that grows by `per_level` for every level above the first
+fn level_scale(level: u32, per_level: f32) -> f32 {
+    1.0 + per_level * level.saturating_sub(1) as f32
+}
+
+/// Gun on top of a tower that has to turn to face its target before firing.
+/// Lives on a child entity of the tower so it can rotate independently.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Turret {
+    /// Current heading in radians, counter-clockwise from +x
+    pub facing: f32,
+    /// Radians per second
+    pub turn_rate: f32,
+    /// How far off target, in radians, the turret can be and still fire
+    pub tolerance: f32,
+}
+
+/// Time until a tower can fire again. Goes negative when a frame overshoots
+/// the next shot so the remainder carries over instead of being lost.
+#[derive(Component, Default)]
+pub struct Reload {
+    pub cooldown: f32,
+}
+
+impl Reload {
+    /// Advance the cooldown by `delta` seconds and return how many shots are
+    /// due at one shot per `interval`. Long frames can make several shots due
+    /// at once. A tower that isn't `ready` (no target, turret not lined up)
+    /// fires nothing and can't bank shots while it waits.
+    pub fn tick(&mut self, delta: f32, interval: f32, ready: bool) -> u32 {
+        self.cooldown -= delta;
+
+        if !ready || interval <= 0.0 {
+            self.cooldown = self.cooldown.max(0.0);
+            return 0;
+        }
+
+        let mut shots = 0;
+        while self.cooldown <= 0.0 {
+            self.cooldown += interval;
+            shots += 1;
+        }
+        shots
+    }
+}
+
+/// Continuous beam a laser tower keeps on one enemy. Damage builds up the
+/// longer it stays locked on, and resets when it switches target.
+#[derive(Component, Default)]
+pub struct Beam {
+    pub target: Option<Entity>,
+    /// Seconds the beam has been on its current target
+    pub locked_for: f32,
+}
+
+impl Beam {
+    /// Seconds on one target before the beam reaches full strength
+    pub const RAMP_SECONDS: f32 = 3.0;
+    /// Damage multiplier at full strength
+    pub const MAX_RAMP: f32 = 3.0;
+
+    /// Point the beam at `target`, or switch it off with `None`. Returns
+    /// whether this started a new lock.
+    pub fn lock(&mut self, target: Option<Entity>, delta: f32) -> bool {
+        if target.is_some() && target == self.target {
+            self.locked_for += delta;
+            return false;
+        }
+
+        self.target = target;
+        self.locked_for = 0.0;
+        target.is_some()
+    }
+
+    /// Damage multiplier from how long the beam has been locked on
+    pub fn ramp(&self) -> f32 {
+        let charge = (self.locked_for / Self::RAMP_SECONDS).min(1.0);
+        1.0 + (Self::MAX_RAMP - 1.0) * charge
+    }
+}
+
+/// Tower stats after all modifiers have been applied
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct EffectiveStats {
+    pub damage: f32,
+    pub range: f32,
+    pub fire_rate: f32,
+    pub reveals_stealth: bool,
+    /// Extra damage fraction against slowed enemies
+    pub slowed_damage: f32,
+    /// Chance from 0 to 1 that a hit is critical
+    pub crit_chance: f32,
+    /// Damage multiplier for critical hits
+    pub crit_multiplier: f32,
+    /// How far each hit's damage can stray either side of `damage`, as a fraction
+    pub damage_spread: f32,
+}
+
+impl EffectiveStats {
+    /// Apply percentage bonuses to a tower's base stats. Bonuses from several
+    /// sources add together rather than compounding.
+    pub fn compute<'a>(
+        base: &Tower,
+        modifiers: impl IntoIterator<Item = &'a StatModifier>,
+    ) -> Self {
+        let total = modifiers
+            .into_iter()
+            .fold(StatModifier::default(), |total, modifier| StatModifier {
+                damage: total.damage + modifier.damage,
+                range: total.range + modifier.range,
+                fire_rate: total.fire_rate + modifier.fire_rate,
+                reveals_stealth: total.reveals_stealth || modifier.reveals_stealth,
+                slowed_damage: total.slowed_damage + modifier.slowed_damage,
+            });
+
+        Self {
+            damage: base.damage * (1.0 + total.damage),
+            range: base.range * (1.0 + total.range),
+            fire_rate: base.fire_rate * (1.0 + total.fire_rate),
+            reveals_stealth: total.reveals_stealth,
+            slowed_damage: total.slowed_damage,
+            crit_chance: base.kind.crit_chance(),
+            crit_multiplier: base.kind.crit_multiplier(),
+            damage_spread: base.kind.damage_spread(),
+        }
+    }
+}
+
+/// Fractional bonuses to tower stats, e.g. 0.2 for +20%
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StatModifier {
+    pub damage: f32,
+    pub range: f32,
+    pub fire_rate: f32,
+    pub reveals_stealth: bool,
+    pub slowed_damage: f32,
+}
+
+/// Buffs every attacking tower within `radius`
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Aura {
+    pub radius: f32,
+    pub modifier: StatModifier,
+}
+
+/// What a tower has achieved in combat
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct CombatRecord {
+    pub kills: u32,
+    pub damage_dealt: f32,
+    pub shots_fired: u32,
+    pub hits: u32,
+    /// Seconds the tower has been in play
+    pub time_active: f32,
+}
+
+impl CombatRecord {
+    const EXPERIENCE_PER_KILL: f32 = 25.0;
+
+    /// Damage dealt plus a bonus per kill, which earns towers their rank and
+    /// the hero its level
+    pub fn experience(&self) -> f32 {
+        self.damage_dealt + self.kills as f32 * Self::EXPERIENCE_PER_KILL
+    }
+
+    /// Fraction of shots fired that hit an enemy
+    pub fn hit_rate(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.hits as f32 / self.shots_fired as f32
+        }
+    }
+
+    /// Average damage per second over the tower's lifetime
+    pub fn dps(&self) -> f32 {
+        if self.time_active <= 0.0 {
+            0.0
+        } else {
+            self.damage_dealt / self.time_active
+        }
+    }
+}
+
+/// Veterancy rank earned through combat, each giving a small stat bonus
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rank(pub u32);
+
+impl Rank {
+    pub const MAX: u32 = 4;
+
+    /// Experience needed to reach each rank
+    const THRESHOLDS: [f32; Rank::MAX as usize] = [300.0, 800.0, 1600.0, 3000.0];
+
+    pub fn for_record(record: &CombatRecord) -> Self {
+        let experience = record.experience();
+        let rank = Self::THRESHOLDS
+            .iter()
+            .filter(|threshold| experience >= **threshold)
+            .count();
+
+        Self(rank as u32)
+    }
+
+    pub fn modifier(self) -> StatModifier {
+        StatModifier {
+            damage: 0.05 * self.0 as f32,
+            fire_rate: 0.03 * self.0 as f32,
+            ..default()
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self.0 {
+            0 => "Recruit",
+            1 => "Veteran",
+            2 => "Elite",
+            3 => "Champion",
+            _ => "Legend",
+        }
+    }
+}
+
+/// Pip above a tower marking one veterancy rank
+#[derive(Component)]
+pub struct RankInsignia;
+
+/// Enemies that towers can only target while inside a stealth-revealing aura
+#[derive(Component)]
+pub struct Stealth;
+
+/// The wave an enemy was spawned in, so the wave can end once they're all gone
+#[derive(Component)]
+pub struct WaveMember(pub u32);
+
+/// Movement penalty on an enemy that wears off when the timer finishes
+#[derive(Component)]
+pub struct Slowed {
+    /// Multiplier on the enemy's speed, e.g. 0.4 to move at 40%
+    pub factor: f32,
+    pub timer: Timer,
+}
+
+impl Enemy {
+    /// How fast the enemy is actually moving along the path right now
+    pub fn current_speed(&self, slowed: Option<&Slowed>) -> f32 {
+        self.speed * slowed.map_or(1.0, |slowed| slowed.factor)
+    }
+}
+
+/// An enemy whose health just ran out. `process_deaths` pays its bounty and
+/// removes it, so a kill is only counted once however many hits landed.
+#[derive(Component, Debug, PartialEq)]
+pub struct Dead {
+    /// Whoever landed the killing blow, if it was a tower or the hero
+    pub killer: Option<Entity>,
+}
+
+/// Player-controlled unit that walks where it's ordered and shoots nearby
+/// enemies. Its kills and damage are kept in a `CombatRecord` like a tower's.
+#[derive(Component)]
+pub struct Hero {
+    pub health: f32,
+    pub level: u32,
+    /// Where the player last ordered the hero to go
+    pub destination: Option<Vec2>,
+    /// Counts down while the hero is dead
+    pub respawn: Option<Timer>,
+}
+
+impl Hero {
+    pub const MAX_LEVEL: u32 = 4;
+    pub const SPEED: f32 = 110.0;
+    pub const RANGE: f32 = 90.0;
+    pub const FIRE_RATE: f32 = 1.5;
+    pub const RESPAWN_SECONDS: f32 = 8.0;
+
+    /// Experience needed for levels 2, 3 and 4
+    const THRESHOLDS: [f32; Hero::MAX_LEVEL as usize - 1] = [150.0, 500.0, 1200.0];
+
+    pub fn level_for(record: &CombatRecord) -> u32 {
+        let experience = record.experience();
+        1 + Self::THRESHOLDS
+            .iter()
+            .filter(|threshold| experience >= **threshold)
+            .count() as u32
+    }
+
+    pub fn max_health(level: u32) -> f32 {
+        100.0 * level_scale(level, 0.25)
+    }
+
+    pub fn damage(&self) -> f32 {
+        12.0 * level_scale(self.level, 0.3)
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.respawn.is_none()
+    }
+
+    pub fn has_skill(&self, skill: HeroSkill) -> bool {
+        self.level >= skill.unlock_level()
+    }
+
+    /// Skills the hero has unlocked so far
+    pub fn skills(&self) -> impl Iterator<Item = HeroSkill> + '_ {
+        HeroSkill::ALL
+            .into_iter()
+            .filter(|skill| self.has_skill(*skill))
+    }
+}
+
+impl Default for Hero {
+    fn default() -> Self {
+        Self {
+            health: Self::max_health(1),
+            level: 1,
+            destination: None,
+            respawn: None,
+        }
+    }
+}
+
+/// Passive skills the hero unlocks as it levels up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeroSkill {
+    /// Each attack fires at two enemies
+    Multishot,
+    /// Can target stealthed enemies
+    TrueSight,
+    /// Slowly heals while alive
+    Regeneration,
+}
+
+impl HeroSkill {
+    pub const ALL: [HeroSkill; 3] = [
+        HeroSkill::Multishot,
+        HeroSkill::TrueSight,
+        HeroSkill::Regeneration,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HeroSkill::Multishot => "Multishot",
+            HeroSkill::TrueSight => "True Sight",
+            HeroSkill::Regeneration => "Regeneration",
+        }
+    }
+
+    pub fn unlock_level(self) -> u32 {
+        match self {
+            HeroSkill::Multishot => 2,
+            HeroSkill::TrueSight => 3,
+            HeroSkill::Regeneration => 4,
+        }
+    }
+}
+
+/// Active abilities the player can trigger on a selected tower
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbilityKind {
+    /// Doubles the tower's damage for a few seconds
+    Overcharge,
+    /// Triples the tower's fire rate for a few seconds
+    Barrage,
+    /// Slows every enemy inside the tower's aura
+    FreezePulse,
+}
+
+impl AbilityKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            AbilityKind::Overcharge => "Overcharge",
+            AbilityKind::Barrage => "Barrage",
+            AbilityKind::FreezePulse => "Freeze Pulse",
+        }
+    }
+
+    /// Seconds before the ability can be used again
+    pub fn cooldown(self) -> f32 {
+        match self {
+            AbilityKind::Overcharge => 20.0,
+            AbilityKind::Barrage => 25.0,
+            AbilityKind::FreezePulse => 30.0,
+        }
+    }
+
+    /// Seconds the ability stays active. Instant abilities last zero.
+    pub fn duration(self) -> f32 {
+        match self {
+            AbilityKind::Overcharge => 5.0,
+            AbilityKind::Barrage => 4.0,
+            AbilityKind::FreezePulse => 0.0,
+        }
+    }
+
+    /// Bonus to the tower's own stats while the ability is active
+    pub fn modifier(self) -> StatModifier {
+        match self {
+            AbilityKind::Overcharge => StatModifier {
+                damage: 1.0,
+                ..default()
+            },
+            AbilityKind::Barrage => StatModifier {
+                fire_rate: 2.0,
+                ..default()
+            },
+            AbilityKind::FreezePulse => StatModifier::default(),
+        }
+    }
+}
+
+/// How much a freeze pulse slows enemies, and for how long
+pub const FREEZE_SLOW_FACTOR: f32 = 0.4;
+pub const FREEZE_SLOW_SECONDS: f32 = 3.0;
+
+/// A tower's active ability and how long until it's ready again
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Ability {
+    pub kind: AbilityKind,
+    /// Seconds until the ability can be used again
+    pub cooldown: f32,
+    /// Seconds the ability has left to run
+    pub active: f32,
+}
+
+impl Ability {
+    /// A fresh ability, ready to use straight away
+    pub fn new(kind: AbilityKind) -> Self {
+        Self {
+            kind,
+            cooldown: 0.0,
+            active: 0.0,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown <= 0.0
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active > 0.0
+    }
+
+    /// Start the ability if it's off cooldown. Returns whether it fired.
+    pub fn activate(&mut self) -> bool {
+        if !self.is_ready() {
+            return false;
+        }
+
+        self.cooldown = self.kind.cooldown();
+        self.active = self.kind.duration();
+        true
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        self.cooldown = (self.cooldown - delta).max(0.0);
+        self.active = (self.active - delta).max(0.0);
+    }
+
+    /// How far the ability has recharged, from 0 just after use to 1 when ready
+    pub fn charge(&self) -> f32 {
+        1.0 - self.cooldown / self.kind.cooldown()
+    }
+}
+
+/// The towers the player can build, in build menu order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TowerKind {
+    Gun,
+    Rapid,
+    Sniper,
+    Missile,
+    Cannon,
+    Laser,
+    Tesla,
+    Support,
+    Farm,
+}
+
+impl TowerKind {
+    pub const ALL: [TowerKind; 9] = [
+        TowerKind::Gun,
+        TowerKind::Rapid,
+        TowerKind::Sniper,
+        TowerKind::Missile,
+        TowerKind::Cannon,
+        TowerKind::Laser,
+        TowerKind::Tesla,
+        TowerKind::Support,
+        TowerKind::Farm,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TowerKind::Gun => "Gun",
+            TowerKind::Rapid => "Rapid",
+            TowerKind::Sniper => "Sniper",
+            TowerKind::Missile => "Missile",
+            TowerKind::Cannon => "Cannon",
+            TowerKind::Laser => "Laser",
+            TowerKind::Tesla => "Tesla",
+            TowerKind::Support => "Support",
+            TowerKind::Farm => "Farm",
+        }
+    }
+
+    pub fn cost(self) -> i32 {
+        match self {
+            TowerKind::Gun => 20,
+            TowerKind::Rapid => 30,
+            TowerKind::Sniper => 45,
+            TowerKind::Missile => 35,
+            TowerKind::Cannon => 40,
+            TowerKind::Laser => 45,
+            TowerKind::Tesla => 50,
+            TowerKind::Support => 40,
+            TowerKind::Farm => 50,
+        }
+    }
+
+    /// Price of raising a tower of this kind from `level` to the next one
+    pub fn upgrade_cost(self, level: u32) -> i32 {
+        self.cost() * level as i32
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            TowerKind::Gun => Color::srgb(0.3, 0.7, 1.0),
+            TowerKind::Rapid => Color::srgb(0.3, 0.9, 0.6),
+            TowerKind::Sniper => Color::srgb(0.6, 0.4, 1.0),
+            TowerKind::Missile => Color::srgb(0.9, 0.45, 0.35),
+            TowerKind::Cannon => Color::srgb(0.55, 0.5, 0.45),
+            TowerKind::Laser => Color::srgb(1.0, 0.35, 0.6),
+            TowerKind::Tesla => Color::srgb(0.5, 0.95, 1.0),
+            TowerKind::Support => Color::srgb(1.0, 0.8, 0.3),
+            TowerKind::Farm => Color::srgb(0.55, 0.8, 0.25),
+        }
+    }
+
+    /// Whether this tower shoots at enemies itself
+    pub fn attacks(self) -> bool {
+        self.weapon().is_some()
+    }
+
+    /// The rotating gun this tower aims with, if it has one. Heavy guns turn
+    /// slowly but must line up precisely; light ones snap round.
+    pub fn turret(self) -> Option<Turret> {
+        let (turn_rate, tolerance) = match self {
+            TowerKind::Gun => (4.0, 0.1),
+            TowerKind::Rapid => (8.0, 0.25),
+            TowerKind::Sniper => (1.5, 0.04),
+            // Missiles steer themselves, so the launcher only needs to face roughly the right way
+            TowerKind::Missile => (3.0, 0.6),
+            TowerKind::Cannon => (2.0, 0.08),
+            TowerKind::Laser => (5.0, 0.05),
+            TowerKind::Tesla => (12.0, 0.5),
+            TowerKind::Support | TowerKind::Farm => return None,
+        };
+
+        Some(Turret {
+            facing: 0.0,
+            turn_rate,
+            tolerance,
+        })
+    }
+
+    /// The ability the player can trigger on this tower, if any
+    pub fn ability(self) -> Option<AbilityKind> {
+        match self {
+            TowerKind::Gun => Some(AbilityKind::Overcharge),
+            TowerKind::Rapid => Some(AbilityKind::Barrage),
+            TowerKind::Support => Some(AbilityKind::FreezePulse),
+            TowerKind::Sniper
+            | TowerKind::Missile
+            | TowerKind::Cannon
+            | TowerKind::Laser
+            | TowerKind::Tesla
+            | TowerKind::Farm => None,
+        }
+    }
+
+    /// What this tower attacks with, if it attacks at all
+    pub fn weapon(self) -> Option<Weapon> {
+        match self {
+            TowerKind::Gun | TowerKind::Rapid | TowerKind::Sniper => {
+                Some(Weapon::Projectile(ProjectileKind::Straight))
+            }
+            TowerKind::Missile => Some(Weapon::Projectile(ProjectileKind::Homing {
+                turn_rate: 5.0,
+                flight_time: 2.0,
+            })),
+            TowerKind::Cannon => Some(Weapon::Projectile(ProjectileKind::Shell { radius: 45.0 })),
+            TowerKind::Laser => Some(Weapon::Beam),
+            TowerKind::Tesla => Some(Weapon::Chain {
+                jumps: 3,
+                range: 70.0,
+            }),
+            TowerKind::Support | TowerKind::Farm => None,
+        }
+    }
+
+    /// Chance from 0 to 1 that a hit from this tower is critical. Beams burn
+    /// steadily and never crit.
+    pub fn crit_chance(self) -> f32 {
+        match self {
+            TowerKind::Gun | TowerKind::Missile | TowerKind::Tesla => 0.1,
+            TowerKind::Rapid | TowerKind::Cannon => 0.05,
+            TowerKind::Sniper => 0.25,
+            TowerKind::Laser | TowerKind::Support | TowerKind::Farm => 0.0,
+        }
+    }
+
+    /// Damage multiplier for this tower's critical hits
+    pub fn crit_multiplier(self) -> f32 {
+        match self {
+            TowerKind::Rapid => 1.5,
+            TowerKind::Sniper => 2.5,
+            _ => 2.0,
+        }
+    }
+
+    /// How far this tower's damage varies either side of its listed damage,
+    /// as a fraction
+    pub fn damage_spread(self) -> f32 {
+        match self {
+            TowerKind::Gun => 0.15,
+            TowerKind::Rapid => 0.25,
+            TowerKind::Sniper | TowerKind::Missile => 0.1,
+            TowerKind::Cannon => 0.2,
+            TowerKind::Tesla => 0.3,
+            TowerKind::Laser | TowerKind::Support | TowerKind::Farm => 0.0,
+        }
+    }
+
+    /// How many enemies this tower's shots can pass through before stopping
+    pub fn pierce(self) -> u32 {
+        match self {
+            TowerKind::Sniper => 2,
+            _ => 0,
+        }
+    }
+
+    /// The buff a tower of this kind and level gives its neighbours, if any
+    pub fn aura(self, level: u32) -> Option<Aura> {
+        match self {
+            TowerKind::Support => {
+                let strength = level_scale(level, 0.5);
+                Some(Aura {
+                    radius: self.range_at(level),
+                    modifier: StatModifier {
+                        damage: 0.25 * strength,
+                        range: 0.15 * strength,
+                        fire_rate: 0.2 * strength,
+                        reveals_stealth: true,
+                        ..default()
+                    },
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Money a tower of this kind and level produces at the end of each wave
+    pub fn income(self, level: u32) -> i32 {
+        match self {
+            TowerKind::Farm => 15 + 10 * level.saturating_sub(1) as i32,
+            _ => 0,
+        }
+    }
+
+    /// Number key that selects this tower in the build menu, 1-9
+    pub fn hotkey(self) -> usize {
+        Self::ALL.iter().position(|kind| *kind == self).unwrap_or(0) + 1
+    }
+
+    /// Range of a tower of this kind at `level`, before any bonuses
+    pub fn range_at(self, level: u32) -> f32 {
+        self.tower().range * level_scale(level, 0.1)
+    }
+
+    /// A newly built tower of this kind
+    pub fn tower(self) -> Tower {
+        let (damage, range, fire_rate) = match self {
+            TowerKind::Gun => (25.0, 100.0, 1.0),
+            TowerKind::Rapid => (8.0, 80.0, 4.0),
+            TowerKind::Sniper => (80.0, 220.0, 0.4),
+            TowerKind::Missile => (30.0, 140.0, 0.7),
+            TowerKind::Cannon => (35.0, 110.0, 0.5),
+            // Beams deal damage times fire rate every second
+            TowerKind::Laser => (15.0, 120.0, 1.0),
+            TowerKind::Tesla => (20.0, 100.0, 0.8),
+            // Range doubles as the aura radius so it shows on the range circle
+            TowerKind::Support => (0.0, 110.0, 0.0),
+            TowerKind::Farm => (0.0, 0.0, 0.0),
+        };
+
+        Tower {
+            kind: self,
+            damage,
+            range,
+            fire_rate,
+            targeting: TargetingMode::default(),
+            lead_targeting: true,
+            level: 1,
+        }
+    }
+}
+
+/// Which enemy in range a tower prefers to shoot at
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TargetingMode {
+    /// Furthest along the path
+    First,
+    /// Least far along the path
+    Last,
+    /// Most remaining health
+    Strongest,
+    /// Least remaining health
+    Weakest,
+    #[default]
+    Closest,
+    Fastest,
+}
+
+impl TargetingMode {
+    pub const ALL: [TargetingMode; 6] = [
+        TargetingMode::First,
+        TargetingMode::Last,
+        TargetingMode::Strongest,
+        TargetingMode::Weakest,
+        TargetingMode::Closest,
+        TargetingMode::Fastest,
+    ];
+
+    /// The mode after this one, wrapping around, for cycling through in the UI
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TargetingMode::First => "First",
+            TargetingMode::Last => "Last",
+            TargetingMode::Strongest => "Strongest",
+            TargetingMode::Weakest => "Weakest",
+            TargetingMode::Closest => "Closest",
+            TargetingMode::Fastest => "Fastest",
+        }
+    }
+}
+
+/// Consumable traps built on the enemy path, set off by enemies walking over them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapKind {
+    /// Hurts every enemy that crosses until it wears out
+    Spikes,
+    /// Explodes once, damaging everything nearby
+    Mine,
+    /// Slows every enemy that crosses until it dries up
+    Glue,
+}
+
+impl TrapKind {
+    pub const ALL: [TrapKind; 3] = [TrapKind::Spikes, TrapKind::Mine, TrapKind::Glue];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TrapKind::Spikes => "Spikes",
+            TrapKind::Mine => "Mine",
+            TrapKind::Glue => "Glue",
+        }
+    }
+
+    pub fn cost(self) -> i32 {
+        match self {
+            TrapKind::Spikes => 15,
+            TrapKind::Mine => 25,
+            TrapKind::Glue => 20,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            TrapKind::Spikes => Color::srgb(0.75, 0.75, 0.8),
+            TrapKind::Mine => Color::srgb(0.7, 0.15, 0.1),
+            TrapKind::Glue => Color::srgba(0.8, 0.9, 0.3, 0.7),
+        }
+    }
+
+    /// How many enemies can set the trap off before it's used up
+    pub fn uses(self) -> u32 {
+        match self {
+            TrapKind::Spikes => 8,
+            TrapKind::Mine => 1,
+            TrapKind::Glue => 12,
+        }
+    }
+
+    /// Damage done to each enemy hit
+    pub fn damage(self) -> f32 {
+        match self {
+            TrapKind::Spikes => 20.0,
+            TrapKind::Mine => 80.0,
+            TrapKind::Glue => 0.0,
+        }
+    }
+
+    /// Mines hit everything within this distance, other traps only the enemy
+    /// that crossed them
+    pub fn radius(self) -> f32 {
+        match self {
+            TrapKind::Mine => 50.0,
+            TrapKind::Spikes | TrapKind::Glue => 0.0,
+        }
+    }
+
+    /// Speed multiplier and duration of the slow applied to crossing enemies
+    pub fn slow(self) -> Option<(f32, f32)> {
+        match self {
+            TrapKind::Glue => Some((0.5, 2.0)),
+            TrapKind::Spikes | TrapKind::Mine => None,
+        }
+    }
+
+    /// Key that selects this trap in the build menu, alongside the number keys
+    /// used for towers
+    pub fn hotkey(self) -> KeyCode {
+        match self {
+            TrapKind::Spikes => KeyCode::KeyQ,
+            TrapKind::Mine => KeyCode::KeyW,
+            TrapKind::Glue => KeyCode::KeyE,
+        }
+    }
+
+    pub fn hotkey_label(self) -> char {
+        match self {
+            TrapKind::Spikes => 'Q',
+            TrapKind::Mine => 'W',
+            TrapKind::Glue => 'E',
+        }
+    }
+}
+
+/// A trap on the enemy path
+#[derive(Component)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub uses_left: u32,
+    /// How far along the path the trap sits, comparable with `Enemy::path_progress`
+    pub path_progress: f32,
+}
+
+/// Something the player can pick from the build bar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildChoice {
+    Tower(TowerKind),
+    Trap(TrapKind),
+}
+
+impl BuildChoice {
+    /// Every choice in build bar order: towers, then traps
+    pub fn all() -> impl Iterator<Item = BuildChoice> {
+        TowerKind::ALL
+            .into_iter()
+            .map(BuildChoice::Tower)
+            .chain(TrapKind::ALL.into_iter().map(BuildChoice::Trap))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BuildChoice::Tower(kind) => kind.name(),
+            BuildChoice::Trap(kind) => kind.name(),
+        }
+    }
+
+    pub fn cost(self) -> i32 {
+        match self {
+            BuildChoice::Tower(kind) => kind.cost(),
+            BuildChoice::Trap(kind) => kind.cost(),
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            BuildChoice::Tower(kind) => kind.color(),
+            BuildChoice::Trap(kind) => kind.color(),
+        }
+    }
+
+    pub fn hotkey_label(self) -> String {
+        match self {
+            BuildChoice::Tower(kind) => kind.hotkey().to_string(),
+            BuildChoice::Trap(kind) => kind.hotkey_label().to_string(),
+        }
+    }
+}
+
+/// Translucent preview of the tower that would be built under the cursor
+#[derive(Component)]
+pub struct PlacementGhost;
+
+/// Money sunk into a tower, used to work out what it sells back for
+#[derive(Component)]
+pub struct TowerInvestment {
+    pub spent: i32,
+    pub placed_wave: u32,
+}
+
+/// How a tower attacks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weapon {
+    /// Fires projectiles that fly to the target
+    Projectile(ProjectileKind),
+    /// Burns the target with a continuous beam, kept in a `Beam` component
+    Beam,
+    /// Strikes the target at once, then arcs on to up to `jumps` more enemies,
+    /// each within `range` of the last
+    Chain { jumps: u32, range: f32 },
+}
+
+/// How a projectile flies once fired
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProjectileKind {
+    /// Keeps going in the direction it was fired
+    Straight,
+    /// Turns toward its target by up to `turn_rate` radians per second, and
+    /// fizzles out once it has flown for `flight_time` seconds
+    Homing { turn_rate: f32, flight_time: f32 },
+    /// Flies straight and explodes on impact, hurting every enemy within
+    /// `radius`, less the further they are from the blast
+    Shell { radius: f32 },
+}
+
+/// Jagged bolt drawn along the jumps of a chain lightning strike until it fades
+#[derive(Component)]
+pub struct LightningBolt {
+    pub points: Vec<Vec2>,
+    pub timer: Timer,
 }
 
 #[derive(Component)]
@@ -22,6 +988,65 @@ pub struct Bullet {
     pub damage: f32,
     pub speed: f32,
     pub direction: Vec2,
+    /// The tower that fired this bullet
+    pub source: Entity,
+    /// Extra damage fraction against slowed enemies
+    pub slowed_damage: f32,
+    pub kind: ProjectileKind,
+    /// The enemy a homing projectile is chasing
+    pub target: Option<Entity>,
+    /// Further enemies this bullet can pass through after the next hit
+    pub pierce: u32,
+    /// Enemies this bullet has already hit, so it never hits one twice
+    pub already_hit: Vec<Entity>,
+    /// Whether the damage was rolled as a critical hit when fired
+    pub critical: bool,
+    /// Flight time left before the projectile fizzles out, if it's limited
+    pub lifetime: Option<Timer>,
+}
+
+impl Bullet {
+    /// A plain bullet that flies straight, stops at the first enemy it hits
+    /// and has no bonus against slowed enemies
+    pub fn new(damage: f32, speed: f32, direction: Vec2, source: Entity) -> Self {
+        Self {
+            damage,
+            speed,
+            direction,
+            source,
+            slowed_damage: 0.0,
+            kind: ProjectileKind::Straight,
+            target: None,
+            pierce: 0,
+            already_hit: Vec::new(),
+            critical: false,
+            lifetime: None,
+        }
+    }
+
+    pub fn can_hit(&self, enemy: Entity) -> bool {
+        !self.already_hit.contains(&enemy)
+    }
+
+    /// Note a hit on `enemy`, returning whether the bullet is used up
+    pub fn register_hit(&mut self, enemy: Entity) -> bool {
+        self.already_hit.push(enemy);
+        if self.pierce == 0 {
+            return true;
+        }
+
+        self.pierce -= 1;
+        false
+    }
+
+    /// Damage this bullet does to an enemy, which may be slowed
+    pub fn damage_against(&self, slowed: bool) -> f32 {
+        if slowed {
+            self.damage * (1.0 + self.slowed_damage)
+        } else {
+            self.damage
+        }
+    }
 }
 
 #[allow(dead_code)] // Placeholder for future features
@@ -45,6 +1070,48 @@ pub struct HealthText;
 #[derive(Component)]
 pub struct ScoreText;
 
+#[derive(Component)]
+pub struct StatusText;
+
+#[derive(Component)]
+pub struct TowerPanel;
+
+#[derive(Component)]
+pub struct GameOverScreen;
+
+#[derive(Component)]
+pub struct TowerInfoText;
+
+#[derive(Component)]
+pub struct SellButton;
+
+#[derive(Component)]
+pub struct UpgradeButton;
+
+#[derive(Component)]
+pub struct MoveButton;
+
+/// A tower that was moved recently and can't be moved again until the timer
+/// finishes
+#[derive(Component)]
+pub struct RelocationCooldown(pub Timer);
+
+/// A card in the build bar for choosing which tower or trap to place
+#[derive(Component)]
+pub struct BuildCard(pub BuildChoice);
+
+#[derive(Component)]
+pub struct TargetingButton;
+
+#[derive(Component)]
+pub struct LeadButton;
+
+#[derive(Component)]
+pub struct AbilityButton;
+
+#[derive(Component)]
+pub struct HeroText;
+
 #[derive(Component)]
 pub struct HitEffect {
     pub timer: Timer,
@@ -67,6 +1134,7 @@ mod tests {
             max_health: 100.0,
             speed: 50.0,
             path_progress: 0.0,
+            last_progress: 0.0,
         };
 
         assert_eq!(enemy.health, 100.0);
@@ -78,29 +1146,260 @@ mod tests {
     #[test]
     fn test_tower_creation() {
         let tower = Tower {
+            kind: TowerKind::Gun,
             damage: 25.0,
             range: 100.0,
             fire_rate: 1.0,
-            last_shot: 0.0,
+            targeting: TargetingMode::default(),
+            lead_targeting: true,
+            level: 1,
         };
 
         assert_eq!(tower.damage, 25.0);
         assert_eq!(tower.range, 100.0);
         assert_eq!(tower.fire_rate, 1.0);
-        assert_eq!(tower.last_shot, 0.0);
+        assert_eq!(tower.targeting, TargetingMode::Closest);
+        assert!(tower.lead_targeting);
+        assert_eq!(tower.level, 1);
+    }
+
+    #[test]
+    fn test_tower_kinds() {
+        for (index, kind) in TowerKind::ALL.iter().enumerate() {
+            let tower = kind.tower();
+
+            assert_eq!(tower.kind, *kind);
+            assert_eq!(tower.level, 1);
+            assert!(kind.cost() > 0);
+            assert_eq!(kind.hotkey(), index + 1);
+
+            // Every tower shoots, supports others or makes money
+            if kind.attacks() {
+                assert!(tower.damage > 0.0 && tower.range > 0.0 && tower.fire_rate > 0.0);
+                assert!(kind.turret().is_some());
+            } else {
+                assert!(kind.aura(1).is_some() || kind.income(1) > 0);
+            }
+        }
+
+        // Only nine number keys to go round
+        assert!(TowerKind::ALL.len() <= 9);
+    }
+
+    #[test]
+    fn test_tower_weapons() {
+        assert_eq!(
+            TowerKind::Gun.weapon(),
+            Some(Weapon::Projectile(ProjectileKind::Straight))
+        );
+        assert!(matches!(
+            TowerKind::Missile.weapon(),
+            Some(Weapon::Projectile(ProjectileKind::Homing { turn_rate, flight_time }))
+                if turn_rate > 0.0 && flight_time > 0.0
+        ));
+        assert!(matches!(
+            TowerKind::Cannon.weapon(),
+            Some(Weapon::Projectile(ProjectileKind::Shell { radius })) if radius > 0.0
+        ));
+        assert_eq!(TowerKind::Laser.weapon(), Some(Weapon::Beam));
+        assert!(matches!(
+            TowerKind::Tesla.weapon(),
+            Some(Weapon::Chain { jumps, range }) if jumps > 0 && range > 0.0
+        ));
+        assert_eq!(TowerKind::Farm.weapon(), None);
+    }
+
+    #[test]
+    fn test_support_aura_matches_its_range() {
+        let mut tower = TowerKind::Support.tower();
+        loop {
+            let aura = TowerKind::Support.aura(tower.level).unwrap();
+            assert_eq!(aura.radius, tower.range);
+            if tower.level == Tower::MAX_LEVEL {
+                break;
+            }
+            tower.upgrade();
+        }
+    }
+
+    #[test]
+    fn test_tower_upgrade() {
+        let mut tower = TowerKind::Gun.tower();
+        let base = TowerKind::Gun.tower();
+
+        tower.upgrade();
+        assert_eq!(tower.level, 2);
+        assert!(tower.damage > base.damage);
+        assert!(tower.range > base.range);
+
+        // Upgrades stop at the level cap
+        for _ in 0..10 {
+            tower.upgrade();
+        }
+        assert_eq!(tower.level, Tower::MAX_LEVEL);
+    }
+
+    #[test]
+    fn test_upgrade_costs_grow() {
+        for kind in TowerKind::ALL {
+            assert!(kind.upgrade_cost(2) > kind.upgrade_cost(1));
+        }
+    }
+
+    #[test]
+    fn test_farm_income_grows_with_level() {
+        assert!(TowerKind::Farm.income(1) > 0);
+        assert!(TowerKind::Farm.income(2) > TowerKind::Farm.income(1));
+        assert_eq!(TowerKind::Gun.income(3), 0);
+    }
+
+    #[test]
+    fn test_rank_from_combat_record() {
+        assert_eq!(Rank::for_record(&CombatRecord::default()), Rank(0));
+
+        let record = CombatRecord {
+            kills: 4,
+            damage_dealt: 250.0,
+            ..default()
+        };
+        assert_eq!(Rank::for_record(&record), Rank(1));
+
+        let record = CombatRecord {
+            kills: 100,
+            damage_dealt: 10_000.0,
+            ..default()
+        };
+        assert_eq!(Rank::for_record(&record), Rank(Rank::MAX));
+    }
+
+    #[test]
+    fn test_combat_record_rates() {
+        let record = CombatRecord {
+            damage_dealt: 500.0,
+            shots_fired: 20,
+            hits: 15,
+            time_active: 50.0,
+            ..default()
+        };
+
+        assert_eq!(record.hit_rate(), 0.75);
+        assert_eq!(record.dps(), 10.0);
+    }
+
+    #[test]
+    fn test_combat_record_rates_before_firing() {
+        let record = CombatRecord::default();
+
+        assert_eq!(record.hit_rate(), 0.0);
+        assert_eq!(record.dps(), 0.0);
+    }
+
+    #[test]
+    fn test_combat_record_experience() {
+        let record = CombatRecord {
+            damage_dealt: 100.0,
+            kills: 2,
+            ..default()
+        };
+
+        assert_eq!(CombatRecord::default().experience(), 0.0);
+        assert_eq!(record.experience(), 150.0);
+    }
+
+    #[test]
+    fn test_rank_modifier_grows() {
+        assert_eq!(Rank(0).modifier(), StatModifier::default());
+        assert!(Rank(2).modifier().damage > Rank(1).modifier().damage);
+        assert!(Rank(2).modifier().fire_rate > Rank(1).modifier().fire_rate);
+    }
+
+    #[test]
+    fn test_effective_stats_without_modifiers() {
+        let tower = TowerKind::Gun.tower();
+        let stats = EffectiveStats::compute(&tower, []);
+
+        assert_eq!(stats.damage, tower.damage);
+        assert_eq!(stats.range, tower.range);
+        assert_eq!(stats.fire_rate, tower.fire_rate);
+        assert!(!stats.reveals_stealth);
+    }
+
+    #[test]
+    fn test_effective_stats_modifiers_add_together() {
+        let tower = TowerKind::Gun.tower();
+        let modifiers = [
+            StatModifier {
+                damage: 0.2,
+                range: 0.1,
+                ..default()
+            },
+            StatModifier {
+                damage: 0.3,
+                fire_rate: 0.5,
+                reveals_stealth: true,
+                ..default()
+            },
+        ];
+        let stats = EffectiveStats::compute(&tower, &modifiers);
+
+        assert_eq!(stats.damage, tower.damage * 1.5);
+        assert_eq!(stats.range, tower.range * 1.1);
+        assert_eq!(stats.fire_rate, tower.fire_rate * 1.5);
+        assert!(stats.reveals_stealth);
+    }
+
+    #[test]
+    fn test_targeting_mode_cycles_through_all() {
+        let mut mode = TargetingMode::First;
+        for expected in TargetingMode::ALL.iter().skip(1) {
+            mode = mode.next();
+            assert_eq!(mode, *expected);
+        }
+
+        // Wraps back around to the start
+        assert_eq!(mode.next(), TargetingMode::First);
+    }
+
+    #[test]
+    fn test_tower_investment_creation() {
+        let investment = TowerInvestment {
+            spent: 20,
+            placed_wave: 3,
+        };
+
+        assert_eq!(investment.spent, 20);
+        assert_eq!(investment.placed_wave, 3);
     }
 
     #[test]
     fn test_bullet_creation() {
         let bullet = Bullet {
-            damage: 25.0,
-            speed: 300.0,
-            direction: Vec2::new(1.0, 0.0),
+            slowed_damage: 0.5,
+            ..Bullet::new(25.0, 300.0, Vec2::new(1.0, 0.0), Entity::PLACEHOLDER)
         };
 
         assert_eq!(bullet.damage, 25.0);
         assert_eq!(bullet.speed, 300.0);
         assert_eq!(bullet.direction, Vec2::new(1.0, 0.0));
+        assert_eq!(bullet.source, Entity::PLACEHOLDER);
+        assert_eq!(bullet.damage_against(false), 25.0);
+        assert_eq!(bullet.damage_against(true), 37.5);
+    }
+
+    #[test]
+    fn test_piercing_bullet() {
+        let mut bullet = Bullet {
+            pierce: TowerKind::Sniper.pierce(),
+            ..Bullet::new(80.0, 300.0, Vec2::X, Entity::PLACEHOLDER)
+        };
+        let enemies = [1, 2, 3].map(Entity::from_raw);
+
+        assert!(!bullet.register_hit(enemies[0]));
+        // Still inside the first enemy on the next frame, but can't hit it again
+        assert!(!bullet.can_hit(enemies[0]));
+        assert!(bullet.can_hit(enemies[1]));
+        assert!(!bullet.register_hit(enemies[1]));
+        assert!(bullet.register_hit(enemies[2]));
     }
 
     #[test]
@@ -110,6 +1409,7 @@ mod tests {
             max_health: 100.0,
             speed: 50.0,
             path_progress: 0.0,
+            last_progress: 0.0,
         };
 
         // Simulate taking damage
@@ -142,4 +1442,182 @@ mod tests {
         assert_eq!(particle.timer.duration().as_secs_f32(), 0.5);
         assert!(!particle.timer.finished());
     }
+
+    fn shots_over(seconds: f32, delta: f32, fire_rate: f32) -> u32 {
+        let mut reload = Reload::default();
+        let frames = (seconds / delta).round() as u32;
+        (0..frames)
+            .map(|_| reload.tick(delta, 1.0 / fire_rate, true))
+            .sum()
+    }
+
+    #[test]
+    fn test_reload_same_shots_at_any_frame_rate() {
+        // One shot straight away, then one every 0.125s
+        for delta in [1.0 / 64.0, 0.125, 0.25, 0.5] {
+            assert_eq!(shots_over(2.0, delta, 8.0), 17, "delta {delta}");
+        }
+    }
+
+    #[test]
+    fn test_reload_fires_several_shots_in_a_long_frame() {
+        let mut reload = Reload::default();
+        assert_eq!(reload.tick(0.5, 0.125, true), 5);
+        assert_eq!(reload.tick(0.5, 0.125, true), 4);
+    }
+
+    #[test]
+    fn test_reload_carries_remainder() {
+        // Resetting on every shot would fire every other frame here, at 5 per
+        // second instead of 7
+        let shots = shots_over(10.0, 0.1, 7.0);
+        assert!((70..=71).contains(&shots), "{shots} shots");
+    }
+
+    #[test]
+    fn test_reload_does_not_bank_shots_while_idle() {
+        let mut reload = Reload::default();
+        assert_eq!(reload.tick(10.0, 0.25, false), 0);
+        assert_eq!(reload.cooldown, 0.0);
+        assert_eq!(reload.tick(0.1, 0.25, true), 1);
+    }
+
+    #[test]
+    fn test_beam_ramps_on_one_target() {
+        let enemies = [1, 2].map(Entity::from_raw);
+        let mut beam = Beam::default();
+        assert!(beam.lock(Some(enemies[0]), 0.1));
+        assert_eq!(beam.ramp(), 1.0);
+
+        assert!(!beam.lock(Some(enemies[0]), 1.5));
+        assert!((beam.ramp() - 2.0).abs() < 1e-5);
+
+        // Full strength is capped
+        beam.lock(Some(enemies[0]), 10.0);
+        assert_eq!(beam.ramp(), Beam::MAX_RAMP);
+    }
+
+    #[test]
+    fn test_beam_resets_on_new_target() {
+        let enemies = [1, 2].map(Entity::from_raw);
+        let mut beam = Beam::default();
+        beam.lock(Some(enemies[0]), 0.0);
+        beam.lock(Some(enemies[0]), 2.0);
+
+        assert!(beam.lock(Some(enemies[1]), 0.1));
+        assert_eq!(beam.ramp(), 1.0);
+
+        assert!(!beam.lock(None, 0.1));
+        assert_eq!(beam.target, None);
+        assert_eq!(beam.ramp(), 1.0);
+    }
+
+    #[test]
+    fn test_ability_cooldown_cycle() {
+        let mut ability = Ability::new(AbilityKind::Overcharge);
+        assert!(ability.is_ready());
+        assert!(!ability.is_active());
+
+        assert!(ability.activate());
+        assert!(ability.is_active());
+        assert!(!ability.is_ready());
+        assert_eq!(ability.charge(), 0.0);
+
+        // Can't be triggered again while recharging
+        assert!(!ability.activate());
+
+        ability.tick(AbilityKind::Overcharge.duration());
+        assert!(!ability.is_active());
+        assert!(!ability.is_ready());
+        assert!((ability.charge() - 0.25).abs() < 0.001);
+
+        ability.tick(AbilityKind::Overcharge.cooldown());
+        assert!(ability.is_ready());
+        assert_eq!(ability.charge(), 1.0);
+    }
+
+    #[test]
+    fn test_instant_ability_is_never_active() {
+        let mut ability = Ability::new(AbilityKind::FreezePulse);
+        assert!(ability.activate());
+        assert!(!ability.is_active());
+        assert!(!ability.is_ready());
+    }
+
+    #[test]
+    fn test_slowed_enemy_speed() {
+        let enemy = Enemy {
+            health: 100.0,
+            max_health: 100.0,
+            speed: 50.0,
+            path_progress: 0.0,
+            last_progress: 0.0,
+        };
+        let slowed = Slowed {
+            factor: FREEZE_SLOW_FACTOR,
+            timer: Timer::from_seconds(FREEZE_SLOW_SECONDS, TimerMode::Once),
+        };
+
+        assert_eq!(enemy.current_speed(None), 50.0);
+        assert_eq!(enemy.current_speed(Some(&slowed)), 20.0);
+    }
+
+    #[test]
+    fn test_hero_levels_from_record() {
+        let mut record = CombatRecord::default();
+        assert_eq!(Hero::level_for(&record), 1);
+
+        record.damage_dealt = 100.0;
+        record.kills = 2;
+        assert_eq!(Hero::level_for(&record), 2);
+
+        record.damage_dealt = 5000.0;
+        assert_eq!(Hero::level_for(&record), Hero::MAX_LEVEL);
+    }
+
+    #[test]
+    fn test_hero_skills_unlock_with_level() {
+        let mut hero = Hero::default();
+        assert!(hero.is_alive());
+        assert_eq!(hero.health, Hero::max_health(1));
+        assert_eq!(hero.skills().count(), 0);
+
+        hero.level = 3;
+        assert!(hero.has_skill(HeroSkill::Multishot));
+        assert!(hero.has_skill(HeroSkill::TrueSight));
+        assert!(!hero.has_skill(HeroSkill::Regeneration));
+        assert!(hero.damage() > Hero::default().damage());
+        assert!(Hero::max_health(3) > Hero::max_health(1));
+    }
+
+    #[test]
+    fn test_hero_skills_all_reachable() {
+        for skill in HeroSkill::ALL {
+            assert!((2..=Hero::MAX_LEVEL).contains(&skill.unlock_level()));
+        }
+    }
+
+    #[test]
+    fn test_trap_kinds() {
+        for kind in TrapKind::ALL {
+            assert!(kind.cost() > 0);
+            assert!(kind.uses() > 0);
+            // Every trap does something
+            assert!(kind.damage() > 0.0 || kind.slow().is_some());
+        }
+        assert!(TrapKind::Mine.radius() > 0.0);
+    }
+
+    #[test]
+    fn test_build_choices_have_unique_hotkeys() {
+        let labels: Vec<_> = BuildChoice::all()
+            .map(|choice| choice.hotkey_label())
+            .collect();
+        let mut unique = labels.clone();
+        unique.sort();
+        unique.dedup();
+
+        assert_eq!(labels.len(), TowerKind::ALL.len() + TrapKind::ALL.len());
+        assert_eq!(unique.len(), labels.len());
+    }
 }
@@ -23,20 +23,88 @@ fn main() {
         }))
         .init_resource::<GameState>()
         .init_resource::<WaveTimer>()
+        .init_resource::<GameConfig>()
+        .init_resource::<SelectedTower>()
+        .init_resource::<BuildSelection>()
+        .init_resource::<Relocating>()
+        .init_resource::<StatusMessage>()
+        .init_resource::<CombatHistory>()
+        .init_resource::<GameRng>()
+        .add_event::<WaveCompleted>()
+        .add_event::<LightningStrike>()
         .add_systems(Startup, setup)
         .add_systems(
             Update,
             (
                 spawn_enemies,
-                move_enemies,
+                move_enemies
+                    .after(collision_system)
+                    .after(beam_damage)
+                    .after(chain_lightning),
+                trigger_traps.after(move_enemies),
+                tick_abilities.before(recompute_tower_stats),
+                tick_slowed,
+                tick_relocation_cooldowns,
+                promote_veterans.before(recompute_tower_stats),
+                recompute_tower_stats.before(tower_shooting),
                 tower_shooting,
+                beam_damage.after(tower_shooting),
+                chain_lightning.after(tower_shooting),
                 bullet_movement,
                 collision_system,
-                cleanup_dead_entities,
+                process_deaths
+                    .after(collision_system)
+                    .after(trigger_traps)
+                    .after(beam_damage)
+                    .after(chain_lightning),
+                (detect_wave_completion, farm_income)
+                    .chain()
+                    .after(spawn_enemies)
+                    .after(process_deaths),
+                move_hero,
+                hero_attack,
+                hero_contact_damage,
+                respawn_hero,
+                level_up_hero,
+            )
+                .run_if(game_running),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_build_hotkeys,
+                handle_build_cards,
                 handle_input,
+                handle_relocation.after(handle_input),
+                handle_hero_orders,
+                update_placement_ghost,
+                handle_sell_button,
+                handle_upgrade_button,
+                handle_targeting_button,
+                handle_lead_button,
+                handle_ability_button,
+                handle_move_button,
+                clear_selection,
+                draw_selection,
+                draw_ability_cooldowns,
+                draw_synergy_links,
+                draw_beams,
+                draw_lightning,
+                draw_hero,
+            )
+                .run_if(game_running),
+        )
+        .add_systems(
+            Update,
+            (
                 update_ui,
+                update_tower_panel,
+                update_build_menu,
+                update_status_text,
+                update_hero_text,
                 update_effects,
                 handle_sound_events,
+                show_game_over,
             ),
         )
         .run();
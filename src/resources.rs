@@ -1,3 +1,4 @@
+use crate::components::{BuildChoice, CombatRecord, EffectiveStats, StatModifier, TowerKind};
 use bevy::prelude::*;
 
 #[derive(Resource)]
@@ -20,16 +21,209 @@ impl Default for GameState {
 #[derive(Resource)]
 pub struct WaveTimer {
     pub timer: Timer,
+    pub wave: u32,
+    pub spawned_this_wave: u32,
+    /// The last wave whose enemies have all been killed or got through
+    pub waves_completed: u32,
 }
 
 impl Default for WaveTimer {
     fn default() -> Self {
         Self {
             timer: Timer::from_seconds(2.0, TimerMode::Repeating),
+            wave: 1,
+            spawned_this_wave: 0,
+            waves_completed: 0,
         }
     }
 }
 
+/// Combat records of towers that have been sold, kept for the end-of-game breakdown
+#[derive(Resource, Default)]
+pub struct CombatHistory(pub Vec<(TowerKind, CombatRecord)>);
+
+/// Sent once every enemy of a wave has spawned and been killed or got through
+#[derive(Event)]
+pub struct WaveCompleted {
+    pub wave: u32,
+}
+
+/// The one source of randomness in the game. Everything random is rolled
+/// through it so a run can be replayed exactly from its seed.
+#[derive(Resource, Clone, Debug)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    pub const DEFAULT_SEED: u64 = 0x5EED_7D0C_2024;
+
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next number in the sequence (SplitMix64)
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform number from 0 up to but not including 1
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform number between `min` and `max`
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// True with probability `chance`
+    pub fn chance(&mut self, chance: f32) -> bool {
+        self.next_f32() < chance
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SEED)
+    }
+}
+
+/// Sent when a tower fires chain lightning at `target`; the damage and jumps
+/// are worked out by chain_lightning
+#[derive(Event)]
+pub struct LightningStrike {
+    /// The tower that fired
+    pub source: Entity,
+    pub origin: Vec2,
+    pub target: Entity,
+    pub stats: EffectiveStats,
+    pub jumps: u32,
+    pub range: f32,
+}
+
+/// Tunable gameplay settings that aren't part of the running score
+#[derive(Resource)]
+pub struct GameConfig {
+    /// Fraction of the money spent on a tower that is returned when selling it
+    pub sell_refund_ratio: f32,
+    /// Towers must be placed inside this area
+    pub arena_bounds: Rect,
+    /// Minimum distance from a tower's centre to any segment of the enemy path
+    pub min_path_distance: f32,
+    /// Minimum distance between the centres of two towers
+    pub min_tower_spacing: f32,
+    /// Towers snap to the centres of cells this size
+    pub grid_size: f32,
+    /// Terrain such as water that can't be built on
+    pub blocked_zones: Vec<Rect>,
+    /// Rocks and walls that can't be built on
+    pub obstacles: Vec<Obstacle>,
+    /// Whether obstacles also block towers' line of sight and stop bullets
+    pub obstacles_block_sight: bool,
+    /// Price of moving a placed tower
+    pub relocation_fee: i32,
+    /// Seconds before a moved tower can be moved again
+    pub relocation_cooldown: f32,
+    /// Furthest the cursor can be from the path for a trap to snap onto it
+    pub trap_snap_distance: f32,
+    /// Minimum distance between two traps
+    pub min_trap_spacing: f32,
+    /// Most money all farms together can produce in one wave
+    pub farm_income_cap: i32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            sell_refund_ratio: 0.7,
+            // Leaves room for the build bar along the bottom of the window
+            arena_bounds: Rect::new(-490.0, -300.0, 490.0, 360.0),
+            min_path_distance: 30.0,
+            min_tower_spacing: 60.0,
+            grid_size: 40.0,
+            blocked_zones: BLOCKED_ZONES.to_vec(),
+            obstacles: OBSTACLES.to_vec(),
+            obstacles_block_sight: true,
+            relocation_fee: 15,
+            relocation_cooldown: 20.0,
+            trap_snap_distance: 30.0,
+            min_trap_spacing: 30.0,
+            farm_income_cap: 80,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Whether scenery stands between `from` and `to`, so a tower or the hero
+    /// can't see past it and a projectile can't fly through it
+    pub fn sight_blocked(&self, from: Vec2, to: Vec2) -> bool {
+        self.obstacles_block_sight
+            && self
+                .obstacles
+                .iter()
+                .any(|obstacle| obstacle.blocks(from, to))
+    }
+}
+
+/// A short message shown to the player, e.g. why a tower couldn't be placed
+#[derive(Resource)]
+pub struct StatusMessage {
+    pub text: String,
+    pub timer: Timer,
+}
+
+impl Default for StatusMessage {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(2.0, TimerMode::Once);
+        timer.tick(timer.duration());
+
+        Self {
+            text: String::new(),
+            timer,
+        }
+    }
+}
+
+impl StatusMessage {
+    pub fn show(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.timer.reset();
+    }
+
+    pub fn is_visible(&self) -> bool {
+        !self.timer.finished()
+    }
+}
+
+/// The placed tower currently shown in the tower panel, if any
+#[derive(Resource, Default)]
+pub struct SelectedTower(pub Option<Entity>);
+
+/// A placed tower the player has picked up to move. While set, clicking the
+/// arena puts it down instead of building.
+#[derive(Resource, Default)]
+pub struct Relocating(pub Option<Entity>);
+
+/// The tower or trap that clicking on empty ground will build, if any
+#[derive(Resource)]
+pub struct BuildSelection(pub Option<BuildChoice>);
+
+impl Default for BuildSelection {
+    fn default() -> Self {
+        Self(Some(BuildChoice::Tower(TowerKind::Gun)))
+    }
+}
+
+pub const ENEMIES_PER_WAVE: u32 = 10;
+
+/// Where the hero starts and comes back after dying
+pub const HERO_SPAWN: Vec2 = Vec2::new(-250.0, 20.0);
+
 pub const ENEMY_PATH: [(f32, f32); 6] = [
     (-400.0, 200.0),
     (-200.0, 200.0),
@@ -39,10 +233,186 @@ pub const ENEMY_PATH: [(f32, f32); 6] = [
     (400.0, 100.0),
 ];
 
+/// Ponds that towers can't be built on, clear of the enemy path
+pub const BLOCKED_ZONES: [Rect; 2] = [
+    Rect {
+        min: Vec2::new(-420.0, -260.0),
+        max: Vec2::new(-300.0, -160.0),
+    },
+    Rect {
+        min: Vec2::new(0.0, 120.0),
+        max: Vec2::new(120.0, 230.0),
+    },
+];
+
+/// A bonus a tower gets for being built near a tower of another kind
+pub struct Synergy {
+    pub name: &'static str,
+    /// The tower that gets the bonus
+    pub beneficiary: TowerKind,
+    /// The kind of tower it has to be near
+    pub partner: TowerKind,
+    /// Furthest apart the two towers can be, centre to centre
+    pub distance: f32,
+    pub modifier: StatModifier,
+    /// Colour of the link drawn between the two towers
+    pub color: Color,
+}
+
+/// Tower combos. Each one applies once however many partners are in range.
+pub const SYNERGIES: [Synergy; 3] = [
+    // Frozen enemies shatter under heavy sniper rounds
+    Synergy {
+        name: "Shatter",
+        beneficiary: TowerKind::Sniper,
+        partner: TowerKind::Support,
+        distance: 120.0,
+        modifier: StatModifier {
+            damage: 0.0,
+            range: 0.0,
+            fire_rate: 0.0,
+            reveals_stealth: false,
+            slowed_damage: 0.5,
+        },
+        color: Color::srgb(0.6, 0.85, 1.0),
+    },
+    Synergy {
+        name: "Crossfire",
+        beneficiary: TowerKind::Rapid,
+        partner: TowerKind::Gun,
+        distance: 90.0,
+        modifier: StatModifier {
+            damage: 0.2,
+            range: 0.0,
+            fire_rate: 0.0,
+            reveals_stealth: false,
+            slowed_damage: 0.0,
+        },
+        color: Color::srgb(1.0, 0.5, 0.3),
+    },
+    Synergy {
+        name: "Spotter",
+        beneficiary: TowerKind::Gun,
+        partner: TowerKind::Sniper,
+        distance: 120.0,
+        modifier: StatModifier {
+            damage: 0.0,
+            range: 0.15,
+            fire_rate: 0.0,
+            reveals_stealth: false,
+            slowed_damage: 0.0,
+        },
+        color: Color::srgb(0.7, 0.5, 1.0),
+    },
+];
+
+/// Solid scenery that towers can't be built on or, optionally, shoot through
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Obstacle {
+    Rock { center: Vec2, radius: f32 },
+    Wall(Rect),
+}
+
+impl Obstacle {
+    /// Whether `point` is inside the obstacle or within `margin` of it
+    pub fn contains(&self, point: Vec2, margin: f32) -> bool {
+        match self {
+            Obstacle::Rock { center, radius } => point.distance(*center) <= radius + margin,
+            Obstacle::Wall(rect) => rect.inflate(margin).contains(point),
+        }
+    }
+
+    /// Whether the straight line from `from` to `to` passes through the obstacle
+    pub fn blocks(&self, from: Vec2, to: Vec2) -> bool {
+        match self {
+            Obstacle::Rock { center, radius } => {
+                let segment = to - from;
+                let t = if segment.length_squared() == 0.0 {
+                    0.0
+                } else {
+                    ((*center - from).dot(segment) / segment.length_squared()).clamp(0.0, 1.0)
+                };
+                center.distance(from + segment * t) <= *radius
+            }
+            Obstacle::Wall(rect) => {
+                // Clip the segment against the rectangle one axis at a time
+                let delta = to - from;
+                let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+
+                for axis in 0..2 {
+                    let (start, step) = (from[axis], delta[axis]);
+                    let (min, max) = (rect.min[axis], rect.max[axis]);
+
+                    if step == 0.0 {
+                        if start < min || start > max {
+                            return false;
+                        }
+                    } else {
+                        let t1 = (min - start) / step;
+                        let t2 = (max - start) / step;
+                        enter = enter.max(t1.min(t2));
+                        exit = exit.min(t1.max(t2));
+                    }
+                }
+
+                enter <= exit
+            }
+        }
+    }
+}
+
+/// Rocks and walls scattered around the arena, clear of the enemy path
+pub const OBSTACLES: [Obstacle; 4] = [
+    Obstacle::Rock {
+        center: Vec2::new(-60.0, 60.0),
+        radius: 22.0,
+    },
+    Obstacle::Rock {
+        center: Vec2::new(300.0, -30.0),
+        radius: 18.0,
+    },
+    Obstacle::Wall(Rect {
+        min: Vec2::new(40.0, -30.0),
+        max: Vec2::new(160.0, -15.0),
+    }),
+    Obstacle::Wall(Rect {
+        min: Vec2::new(-120.0, 240.0),
+        max: Vec2::new(-105.0, 330.0),
+    }),
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_rng_is_reproducible_from_seed() {
+        let mut a = GameRng::new(42);
+        let mut b = GameRng::new(42);
+        let mut c = GameRng::new(43);
+
+        let first: Vec<_> = (0..10).map(|_| a.next_u64()).collect();
+        let second: Vec<_> = (0..10).map(|_| b.next_u64()).collect();
+        let other: Vec<_> = (0..10).map(|_| c.next_u64()).collect();
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_rng_ranges() {
+        let mut rng = GameRng::default();
+        for _ in 0..1000 {
+            let value = rng.next_f32();
+            assert!((0.0..1.0).contains(&value));
+
+            let value = rng.range(5.0, 10.0);
+            assert!((5.0..=10.0).contains(&value));
+        }
+
+        assert!(!(0..100).any(|_| rng.chance(0.0)));
+        assert!((0..100).all(|_| rng.chance(1.0)));
+    }
+
     #[test]
     fn test_game_state_default() {
         let game_state = GameState::default();
@@ -79,6 +449,99 @@ mod tests {
 
         assert_eq!(wave_timer.timer.duration().as_secs_f32(), 2.0);
         assert!(!wave_timer.timer.finished());
+        assert_eq!(wave_timer.wave, 1);
+        assert_eq!(wave_timer.spawned_this_wave, 0);
+        assert_eq!(wave_timer.waves_completed, 0);
+    }
+
+    #[test]
+    fn test_game_config_default() {
+        let config = GameConfig::default();
+
+        assert!(config.sell_refund_ratio > 0.0 && config.sell_refund_ratio <= 1.0);
+        assert!(config.min_path_distance > 0.0);
+        assert!(config.min_tower_spacing > 0.0);
+        assert!(config.grid_size > 0.0);
+        assert_eq!(config.blocked_zones.len(), BLOCKED_ZONES.len());
+        assert_eq!(config.obstacles.len(), OBSTACLES.len());
+        assert!(config.obstacles_block_sight);
+        assert!(config.farm_income_cap > 0);
+        assert!(config.trap_snap_distance > 0.0);
+        assert!(config.relocation_fee > 0);
+        assert!(config.relocation_cooldown > 0.0);
+        assert!(config.min_trap_spacing > 0.0);
+    }
+
+    #[test]
+    fn test_build_selection_default() {
+        assert_eq!(
+            BuildSelection::default().0,
+            Some(BuildChoice::Tower(TowerKind::Gun))
+        );
+    }
+
+    #[test]
+    fn test_status_message() {
+        let mut status = StatusMessage::default();
+        assert!(!status.is_visible());
+
+        status.show("Can't build there");
+        assert!(status.is_visible());
+        assert_eq!(status.text, "Can't build there");
+
+        use std::time::Duration;
+        status.timer.tick(Duration::from_secs_f32(2.5));
+        assert!(!status.is_visible());
+    }
+
+    #[test]
+    fn test_rock_blocks_lines_through_it() {
+        let rock = Obstacle::Rock {
+            center: Vec2::ZERO,
+            radius: 10.0,
+        };
+
+        assert!(rock.contains(Vec2::new(5.0, 5.0), 0.0));
+        assert!(!rock.contains(Vec2::new(15.0, 0.0), 0.0));
+        assert!(rock.contains(Vec2::new(15.0, 0.0), 6.0));
+
+        assert!(rock.blocks(Vec2::new(-50.0, 5.0), Vec2::new(50.0, 5.0)));
+        assert!(!rock.blocks(Vec2::new(-50.0, 20.0), Vec2::new(50.0, 20.0)));
+        // Stops short of the rock
+        assert!(!rock.blocks(Vec2::new(-50.0, 0.0), Vec2::new(-20.0, 0.0)));
+    }
+
+    #[test]
+    fn test_wall_blocks_lines_through_it() {
+        let wall = Obstacle::Wall(Rect::new(-5.0, -50.0, 5.0, 50.0));
+
+        assert!(wall.contains(Vec2::ZERO, 0.0));
+        assert!(wall.contains(Vec2::new(8.0, 0.0), 4.0));
+
+        assert!(wall.blocks(Vec2::new(-40.0, 0.0), Vec2::new(40.0, 10.0)));
+        // Passes over the end of the wall
+        assert!(!wall.blocks(Vec2::new(-40.0, 60.0), Vec2::new(40.0, 70.0)));
+        // Parallel to the wall, beside it
+        assert!(!wall.blocks(Vec2::new(20.0, -80.0), Vec2::new(20.0, 80.0)));
+        // Doesn't reach the wall
+        assert!(!wall.blocks(Vec2::new(-40.0, 0.0), Vec2::new(-10.0, 0.0)));
+    }
+
+    #[test]
+    fn test_sight_blocked_only_when_obstacles_block_sight() {
+        let mut config = GameConfig {
+            obstacles: vec![Obstacle::Rock {
+                center: Vec2::ZERO,
+                radius: 10.0,
+            }],
+            ..default()
+        };
+
+        assert!(config.sight_blocked(Vec2::new(-50.0, 0.0), Vec2::new(50.0, 0.0)));
+        assert!(!config.sight_blocked(Vec2::new(-50.0, 30.0), Vec2::new(50.0, 30.0)));
+
+        config.obstacles_block_sight = false;
+        assert!(!config.sight_blocked(Vec2::new(-50.0, 0.0), Vec2::new(50.0, 0.0)));
     }
 
     #[test]
@@ -138,11 +601,19 @@ mod tests {
         );
 
         // Test that initial money allows for tower placement
-        let tower_cost = 20;
-        let max_towers = game_state.money / tower_cost;
+        let max_towers = game_state.money / TowerKind::Gun.cost();
         assert!(
             max_towers >= 1,
             "Should be able to place at least one tower initially"
         );
     }
+
+    #[test]
+    fn test_synergies_pair_an_attacking_beneficiary_with_another_kind() {
+        for synergy in &SYNERGIES {
+            assert_ne!(synergy.beneficiary, synergy.partner, "{}", synergy.name);
+            assert!(synergy.beneficiary.attacks(), "{}", synergy.name);
+            assert!(synergy.distance >= GameConfig::default().min_tower_spacing);
+        }
+    }
 }
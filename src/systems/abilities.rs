@@ -0,0 +1,125 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+/// Trigger the selected tower's ability. Freeze pulses take effect at once;
+/// timed abilities are picked up by the stat layer while they run.
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
+pub fn handle_ability_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<AbilityButton>)>,
+    mut tower_query: Query<(&Transform, &mut Ability, Option<&Aura>)>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    selected: Res<SelectedTower>,
+    mut status: ResMut<StatusMessage>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !interaction_query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    let Some((transform, mut ability, aura)) = selected
+        .0
+        .and_then(|entity| tower_query.get_mut(entity).ok())
+    else {
+        return;
+    };
+
+    if !ability.activate() {
+        status.show(format!(
+            "{} ready in {:.0}s",
+            ability.kind.name(),
+            ability.cooldown.ceil()
+        ));
+        return;
+    }
+
+    if ability.kind == AbilityKind::FreezePulse {
+        let position = transform.translation.truncate();
+        let radius = aura.map_or(0.0, |aura| aura.radius);
+
+        for (enemy, enemy_transform) in enemy_query.iter() {
+            if position.distance(enemy_transform.translation.truncate()) <= radius {
+                commands.entity(enemy).insert(Slowed {
+                    factor: FREEZE_SLOW_FACTOR,
+                    timer: Timer::from_seconds(FREEZE_SLOW_SECONDS, TimerMode::Once),
+                });
+            }
+        }
+
+        // Icy flash covering the area of the pulse
+        commands.spawn((
+            Mesh2d(meshes.add(Circle::new(radius))),
+            MeshMaterial2d(materials.add(Color::srgba(0.6, 0.85, 1.0, 0.35))),
+            Transform::from_translation(position.extend(0.5)),
+            HitEffect {
+                timer: Timer::from_seconds(0.4, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+/// Count down ability cooldowns. Only the end of an active period counts as a
+/// change, so effective stats aren't rebuilt every frame while abilities recharge.
+pub fn tick_abilities(mut ability_query: Query<&mut Ability>, time: Res<Time>) {
+    for mut ability in ability_query.iter_mut() {
+        let was_active = ability.is_active();
+        ability.bypass_change_detection().tick(time.delta_secs());
+
+        if was_active && !ability.is_active() {
+            ability.set_changed();
+        }
+    }
+}
+
+/// Let slows wear off
+pub fn tick_slowed(
+    mut slowed_query: Query<(Entity, &mut Slowed)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut slowed) in slowed_query.iter_mut() {
+        slowed.timer.tick(time.delta());
+        if slowed.timer.finished() {
+            commands.entity(entity).remove::<Slowed>();
+        }
+    }
+}
+
+/// Ring around each tower with an ability: an arc that fills as it recharges,
+/// a full gold ring when it's ready and a bright one while it's running
+pub fn draw_ability_cooldowns(
+    tower_query: Query<(&Transform, &Ability), With<Tower>>,
+    enemy_query: Query<&Transform, (With<Enemy>, With<Slowed>)>,
+    mut gizmos: Gizmos,
+) {
+    const RADIUS: f32 = 19.0;
+
+    for (transform, ability) in tower_query.iter() {
+        let position = transform.translation.truncate();
+
+        if ability.is_active() {
+            gizmos.circle_2d(position, RADIUS, Color::srgb(1.0, 0.4, 0.2));
+        } else if ability.is_ready() {
+            gizmos.circle_2d(position, RADIUS, Color::srgba(1.0, 0.85, 0.2, 0.8));
+        } else {
+            gizmos.arc_2d(
+                Isometry2d::from_translation(position),
+                TAU * ability.charge(),
+                RADIUS,
+                Color::srgba(0.7, 0.7, 0.7, 0.6),
+            );
+        }
+    }
+
+    // Frost rings on slowed enemies
+    for transform in enemy_query.iter() {
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            13.0,
+            Color::srgb(0.6, 0.85, 1.0),
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use super::combat::damage_enemy;
+use crate::components::*;
+use bevy::prelude::*;
+
+/// Damage a beam does in one second: the tower's damage times its fire rate,
+/// scaled up the longer it has been locked on
+pub fn beam_damage_per_second(stats: &EffectiveStats, beam: &Beam, slowed: bool) -> f32 {
+    let base = stats.damage * stats.fire_rate * beam.ramp();
+    if slowed {
+        base * (1.0 + stats.slowed_damage)
+    } else {
+        base
+    }
+}
+
+/// Burn enemies that laser towers are locked on to. Runs after towers aim, so
+/// beams only hurt enemies that are in range and in sight this frame.
+pub fn beam_damage(
+    mut tower_query: Query<(Entity, &Beam, &EffectiveStats, &mut CombatRecord)>,
+    mut enemy_query: Query<(&mut Enemy, Has<Slowed>)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (tower, beam, stats, mut record) in tower_query.iter_mut() {
+        let Some(target) = beam.target else {
+            continue;
+        };
+
+        let Ok((mut enemy, slowed)) = enemy_query.get_mut(target) else {
+            continue;
+        };
+
+        let damage = beam_damage_per_second(stats, beam, slowed) * time.delta_secs();
+        record.damage_dealt += damage_enemy(&mut commands, target, &mut enemy, damage, Some(tower));
+    }
+}
+
+/// A line from each firing laser to its target, brightening toward white as
+/// the beam ramps up
+pub fn draw_beams(
+    tower_query: Query<(&Transform, &Beam), With<Tower>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, beam) in tower_query.iter() {
+        let Some(target) = beam.target.and_then(|target| enemy_query.get(target).ok()) else {
+            continue;
+        };
+
+        let start = transform.translation.truncate();
+        let end = target.translation.truncate();
+        let charge = (beam.ramp() - 1.0) / (Beam::MAX_RAMP - 1.0);
+        let color = TowerKind::Laser.color().mix(&Color::WHITE, charge);
+
+        // A second line just off the first makes the beam look thicker
+        let offset = (end - start).perp().normalize_or_zero();
+        gizmos.line_2d(start, end, color);
+        gizmos.line_2d(start + offset, end + offset, color.with_alpha(0.5));
+        gizmos.circle_2d(end, 4.0 + 3.0 * charge, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beam_damage_per_second() {
+        let stats = EffectiveStats::compute(&TowerKind::Laser.tower(), []);
+        let mut beam = Beam::default();
+        beam.lock(Some(Entity::from_raw(1)), 0.0);
+        assert_eq!(beam_damage_per_second(&stats, &beam, false), 15.0);
+
+        beam.lock(Some(Entity::from_raw(1)), Beam::RAMP_SECONDS);
+        assert_eq!(beam_damage_per_second(&stats, &beam, false), 45.0);
+    }
+
+    #[test]
+    fn test_beam_damage_against_slowed() {
+        let stats = EffectiveStats {
+            slowed_damage: 0.5,
+            ..EffectiveStats::compute(&TowerKind::Laser.tower(), [])
+        };
+        let beam = Beam::default();
+        assert_eq!(beam_damage_per_second(&stats, &beam, true), 22.5);
+    }
+}
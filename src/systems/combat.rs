@@ -1,18 +1,234 @@
 use super::sound::{create_sound_effect_visual, play_console_beep, SoundType};
+use super::targeting::rotate_towards;
 use crate::components::*;
 use crate::resources::*;
 use bevy::prelude::*;
 
+/// Enemies a homing projectile can chase, kept apart from bullet transforms
+type HomingTarget = (With<Enemy>, Without<Bullet>);
+
+/// Share of the full damage an explosion still does at the edge of its radius
+const SPLASH_EDGE_DAMAGE: f32 = 0.3;
+
+/// How far a homing projectile looks for a new target when its own dies
+const HOMING_RETARGET_RANGE: f32 = 120.0;
+
+/// Turn a flight direction toward `desired` by at most `max_turn` radians
+pub fn steer(direction: Vec2, desired: Vec2, max_turn: f32) -> Vec2 {
+    if desired == Vec2::ZERO {
+        return direction;
+    }
+
+    Vec2::from_angle(rotate_towards(
+        direction.to_angle(),
+        desired.to_angle(),
+        max_turn,
+    ))
+}
+
+/// The closest enemy within `range` of `position` for a homing projectile to
+/// chase once its target is gone
+pub fn retarget(
+    position: Vec2,
+    enemies: impl IntoIterator<Item = (Entity, Vec2)>,
+    range: f32,
+) -> Option<Entity> {
+    enemies
+        .into_iter()
+        .map(|(entity, enemy)| (entity, position.distance(enemy)))
+        .filter(|(_, distance)| *distance <= range)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Share of an explosion's damage taken at `distance` from its centre: all of
+/// it in the middle, falling off linearly toward the edge, none outside
+pub fn splash_falloff(distance: f32, radius: f32) -> f32 {
+    if radius <= 0.0 || distance > radius {
+        return 0.0;
+    }
+
+    1.0 - (1.0 - SPLASH_EDGE_DAMAGE) * distance / radius
+}
+
+/// Flash of fire covering the area an explosion reaches
+pub fn spawn_explosion(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    position: Vec2,
+    radius: f32,
+) {
+    commands.spawn((
+        Mesh2d(meshes.add(Circle::new(radius))),
+        MeshMaterial2d(materials.add(Color::srgba(1.0, 0.5, 0.1, 0.5))),
+        Transform::from_translation(position.extend(2.0)),
+        HitEffect {
+            timer: Timer::from_seconds(0.3, TimerMode::Once),
+        },
+    ));
+}
+
+/// Roll the damage of one hit from a tower with `stats`: somewhere within its
+/// spread, multiplied up if it comes up critical
+pub fn roll_damage(stats: &EffectiveStats, rng: &mut GameRng) -> (f32, bool) {
+    let spread = stats.damage_spread;
+    let mut damage = stats.damage * rng.range(1.0 - spread, 1.0 + spread);
+
+    let critical = rng.chance(stats.crit_chance);
+    if critical {
+        damage *= stats.crit_multiplier;
+    }
+
+    (damage, critical)
+}
+
+/// Flash on an enemy that was hit. Critical hits get a bigger white-hot
+/// starburst so they stand out from ordinary hits.
+pub fn spawn_hit_effect(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    position: Vec3,
+    critical: bool,
+) {
+    let (mesh, color, seconds) = if critical {
+        (
+            meshes.add(RegularPolygon::new(14.0, 4)),
+            Color::srgb(1.0, 0.95, 0.8),
+            0.35,
+        )
+    } else {
+        (
+            meshes.add(Circle::new(8.0)),
+            Color::srgb(1.0, 0.8, 0.2),
+            0.2,
+        )
+    };
+
+    commands.spawn((
+        Mesh2d(mesh),
+        MeshMaterial2d(materials.add(color)),
+        Transform::from_translation(position.with_z(2.0)),
+        HitEffect {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        },
+    ));
+
+    if critical {
+        // A second starburst turned halfway makes an eight-pointed flash
+        commands.spawn((
+            Mesh2d(meshes.add(RegularPolygon::new(11.0, 4))),
+            MeshMaterial2d(materials.add(Color::srgb(1.0, 0.3, 0.2))),
+            Transform::from_translation(position.with_z(2.1))
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            HitEffect {
+                timer: Timer::from_seconds(seconds, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+/// Grey puff where a projectile was stopped or burnt out
+fn spawn_fizzle(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    position: Vec3,
+) {
+    commands.spawn((
+        Mesh2d(meshes.add(Circle::new(5.0))),
+        MeshMaterial2d(materials.add(Color::srgba(0.7, 0.7, 0.7, 0.8))),
+        Transform::from_translation(position.with_z(2.0)),
+        HitEffect {
+            timer: Timer::from_seconds(0.15, TimerMode::Once),
+        },
+    ));
+}
+
+/// Fly projectiles along their direction. Homing ones turn toward their target
+/// first, pick the closest visible enemy nearby if it died, and fizzle out if
+/// there's nothing left to chase.
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
 pub fn bullet_movement(
-    mut bullet_query: Query<(Entity, &mut Transform, &Bullet)>,
+    mut bullet_query: Query<(Entity, &mut Transform, &mut Bullet)>,
+    enemy_query: Query<(Entity, &Transform, Has<Stealth>), HomingTarget>,
     time: Res<Time>,
+    config: Res<GameConfig>,
     mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for (entity, mut transform, bullet) in bullet_query.iter_mut() {
+    for (entity, mut transform, mut bullet) in bullet_query.iter_mut() {
+        let previous = transform.translation.truncate();
+
+        // Out of fuel, so a missile circling a target it can't turn tightly
+        // enough to hit doesn't fly forever
+        if let Some(lifetime) = bullet.lifetime.as_mut() {
+            lifetime.tick(time.delta());
+            if lifetime.finished() {
+                commands.entity(entity).despawn();
+                spawn_fizzle(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    transform.translation,
+                );
+                continue;
+            }
+        }
+
+        if let ProjectileKind::Homing { turn_rate, .. } = bullet.kind {
+            let target = bullet
+                .target
+                .and_then(|target| enemy_query.get(target).ok())
+                .map(|(target, _, _)| target)
+                .or_else(|| {
+                    retarget(
+                        previous,
+                        enemy_query
+                            .iter()
+                            .filter(|(_, _, stealth)| !stealth)
+                            .map(|(enemy, transform, _)| (enemy, transform.translation.truncate())),
+                        HOMING_RETARGET_RANGE,
+                    )
+                });
+
+            let Some((_, target_transform, _)) =
+                target.and_then(|target| enemy_query.get(target).ok())
+            else {
+                commands.entity(entity).despawn();
+                spawn_fizzle(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    transform.translation,
+                );
+                continue;
+            };
+
+            bullet.target = target;
+            let desired = target_transform.translation.truncate() - previous;
+            bullet.direction = steer(bullet.direction, desired, turn_rate * time.delta_secs());
+            transform.rotation = Quat::from_rotation_z(bullet.direction.to_angle());
+        }
+
         transform.translation += Vec3::new(bullet.direction.x, bullet.direction.y, 0.0)
             * bullet.speed
             * time.delta_secs();
 
+        // Check the whole step so fast bullets can't skip through thin walls
+        if config.sight_blocked(previous, transform.translation.truncate()) {
+            commands.entity(entity).despawn();
+            spawn_fizzle(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                transform.translation,
+            );
+            continue;
+        }
+
         // Remove bullets that go off screen
         if transform.translation.x.abs() > 600.0 || transform.translation.y.abs() > 400.0 {
             commands.entity(entity).despawn();
@@ -20,84 +236,515 @@ pub fn bullet_movement(
     }
 }
 
+/// Damage enemies struck by projectiles. Most hit the one enemy they touch,
+/// piercing ones carry on through to the next, and shells blow up and hurt
+/// everything around the point of impact.
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
 pub fn collision_system(
-    mut enemy_query: Query<(Entity, &mut Enemy, &Transform), Without<Bullet>>,
-    bullet_query: Query<(Entity, &Bullet, &Transform), Without<Enemy>>,
+    mut enemy_query: Query<(Entity, &mut Enemy, &Transform, Has<Slowed>), Without<Bullet>>,
+    mut bullet_query: Query<(Entity, &mut Bullet, &Transform), Without<Enemy>>,
+    mut record_query: Query<&mut CombatRecord>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (bullet_entity, mut bullet, bullet_transform) in bullet_query.iter_mut() {
+        // Collision threshold
+        let Some(struck) = enemy_query
+            .iter()
+            .find(|(entity, enemy, enemy_transform, _)| {
+                // Enemies killed earlier this frame are left for process_deaths
+                enemy.health > 0.0
+                    && bullet.can_hit(*entity)
+                    && bullet_transform
+                        .translation
+                        .distance(enemy_transform.translation)
+                        < 16.0
+            })
+            .map(|(entity, _, _, _)| entity)
+        else {
+            continue;
+        };
+
+        // A piercing bullet is one shot however many enemies it passes through
+        let first_contact = bullet.already_hit.is_empty();
+        let impact = bullet_transform.translation.truncate();
+        let (victims, spent): (Vec<(Entity, f32)>, bool) = match bullet.kind {
+            ProjectileKind::Shell { radius } => {
+                spawn_explosion(&mut commands, &mut meshes, &mut materials, impact, radius);
+                let victims = enemy_query
+                    .iter()
+                    .filter(|(_, enemy, _, _)| enemy.health > 0.0)
+                    .map(|(entity, _, transform, _)| {
+                        let distance = transform.translation.truncate().distance(impact);
+                        (entity, splash_falloff(distance, radius))
+                    })
+                    .filter(|(_, share)| *share > 0.0)
+                    .collect();
+                (victims, true)
+            }
+            _ => (vec![(struck, 1.0)], bullet.register_hit(struck)),
+        };
+
+        // Remove bullet once it can't pass through any more enemies
+        if spent {
+            commands.entity(bullet_entity).despawn();
+        }
+
+        // The source may have been sold while the bullet was in flight
+        let mut record = record_query.get_mut(bullet.source).ok();
+        if let Some(record) = record.as_mut().filter(|_| first_contact) {
+            record.hits += 1;
+        }
+
+        // Play hit sound and create visual effect, once however many enemies
+        // a blast catches
+        play_console_beep(SoundType::Hit);
+        create_sound_effect_visual(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            bullet_transform.translation,
+            SoundType::Hit,
+        );
+
+        for (victim, share) in victims {
+            let Ok((enemy_entity, mut enemy, enemy_transform, slowed)) =
+                enemy_query.get_mut(victim)
+            else {
+                continue;
+            };
+
+            // Damage enemy, crediting the tower that fired for the damage that landed
+            let damage = bullet.damage_against(slowed) * share;
+            let dealt = damage_enemy(
+                &mut commands,
+                enemy_entity,
+                &mut enemy,
+                damage,
+                Some(bullet.source),
+            );
+
+            if let Some(record) = record.as_mut() {
+                record.damage_dealt += dealt;
+            }
+
+            spawn_hit_effect(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                enemy_transform.translation,
+                bullet.critical,
+            );
+        }
+    }
+}
+
+/// Take `damage` off an enemy, marking it dead if this is the hit that finished
+/// it off. Enemies that are already dead take no more damage, so two hits in
+/// the same frame can't both claim the kill. Returns the damage that landed.
+pub fn damage_enemy(
+    commands: &mut Commands,
+    entity: Entity,
+    enemy: &mut Enemy,
+    damage: f32,
+    killer: Option<Entity>,
+) -> f32 {
+    if enemy.health <= 0.0 {
+        return 0.0;
+    }
+
+    let dealt = damage.min(enemy.health);
+    enemy.health -= damage;
+
+    if enemy.health <= 0.0 {
+        // The enemy may have reached the end of the path earlier this frame
+        commands.entity(entity).try_insert(Dead { killer });
+    }
+
+    dealt
+}
+
+/// Remove dead enemies with a burst of particles, pay out their bounty and
+/// credit the kill. The one place kills are counted.
+pub fn process_deaths(
+    dead_query: Query<(Entity, &Transform, &Dead)>,
+    mut record_query: Query<&mut CombatRecord>,
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for (bullet_entity, bullet, bullet_transform) in bullet_query.iter() {
-        for (enemy_entity, mut enemy, enemy_transform) in enemy_query.iter_mut() {
-            let distance = bullet_transform
-                .translation
-                .distance(enemy_transform.translation);
-
-            if distance < 16.0 {
-                // Collision threshold
-                // Damage enemy
-                enemy.health -= bullet.damage;
-
-                // Remove bullet
-                commands.entity(bullet_entity).despawn();
-
-                // Play hit sound and create visual effect
-                play_console_beep(SoundType::Hit);
-                create_sound_effect_visual(
-                    &mut commands,
-                    &mut meshes,
-                    &mut materials,
-                    enemy_transform.translation,
-                    SoundType::Hit,
-                );
+    for (enemy, transform, dead) in dead_query.iter() {
+        let position = transform.translation;
+
+        // Play death sound and create visual effect
+        play_console_beep(SoundType::Death);
+        create_sound_effect_visual(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            position,
+            SoundType::Death,
+        );
+
+        // Create explosion effect
+        for i in 0..6 {
+            let angle = i as f32 * std::f32::consts::PI * 2.0 / 6.0;
+            let offset = Vec3::new(angle.cos() * 15.0, angle.sin() * 15.0, 2.0);
+            commands.spawn((
+                Mesh2d(meshes.add(Circle::new(3.0))),
+                MeshMaterial2d(materials.add(Color::srgb(1.0, 0.4, 0.1))),
+                Transform::from_translation(position + offset),
+                ExplosionParticle {
+                    velocity: Vec2::new(angle.cos() * 80.0, angle.sin() * 80.0),
+                    timer: Timer::from_seconds(0.5, TimerMode::Once),
+                },
+            ));
+        }
+
+        commands.entity(enemy).despawn();
+        game_state.score += 10;
+        game_state.money += 5;
+
+        // The killer may have been sold since
+        if let Some(mut record) = dead
+            .killer
+            .and_then(|killer| record_query.get_mut(killer).ok())
+        {
+            record.kills += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::enemy::{calculate_total_path_length, move_enemies};
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::f32::consts::FRAC_PI_2;
+    use std::time::Duration;
+
+    /// Just enough of the game to land bullets and count the dead
+    fn combat_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameState>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .add_systems(
+                Update,
+                (collision_system, process_deaths.after(collision_system)),
+            );
+        app
+    }
+
+    fn spawn_enemy(app: &mut App, health: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Enemy {
+                    health,
+                    max_health: health,
+                    speed: 50.0,
+                    path_progress: 0.0,
+                    last_progress: 0.0,
+                },
+                Transform::default(),
+            ))
+            .id()
+    }
+
+    fn spawn_bullet(app: &mut App, source: Entity, damage: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Bullet::new(damage, 300.0, Vec2::X, source),
+                Transform::from_xyz(2.0, 0.0, 0.0),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn test_simultaneous_lethal_hits_kill_once() {
+        let mut app = combat_app();
+        let tower = app.world_mut().spawn(CombatRecord::default()).id();
+        let enemy = spawn_enemy(&mut app, 10.0);
+        let first = spawn_bullet(&mut app, tower, 25.0);
+        let second = spawn_bullet(&mut app, tower, 25.0);
+        let before = GameState::default();
+
+        app.update();
+
+        let state = app.world().resource::<GameState>();
+        assert_eq!(state.score, before.score + 10);
+        assert_eq!(state.money, before.money + 5);
+        assert!(app.world().get_entity(enemy).is_err());
+
+        let record = app.world().get::<CombatRecord>(tower).unwrap();
+        assert_eq!(record.kills, 1);
+        assert_eq!(record.hits, 1);
+        assert_eq!(record.damage_dealt, 10.0);
+
+        // Only one bullet was spent; the other flies on
+        let remaining = [first, second]
+            .iter()
+            .filter(|bullet| app.world().get_entity(**bullet).is_ok())
+            .count();
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn test_simultaneous_hits_from_two_towers_credit_one_killer() {
+        let mut app = combat_app();
+        let towers = [
+            app.world_mut().spawn(CombatRecord::default()).id(),
+            app.world_mut().spawn(CombatRecord::default()).id(),
+        ];
+        spawn_enemy(&mut app, 40.0);
+        spawn_bullet(&mut app, towers[0], 25.0);
+        spawn_bullet(&mut app, towers[1], 25.0);
+
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<GameState>().score,
+            GameState::default().score + 10
+        );
+
+        let records: Vec<_> = towers
+            .iter()
+            .map(|tower| app.world().get::<CombatRecord>(*tower).unwrap())
+            .collect();
+        assert_eq!(records.iter().map(|record| record.kills).sum::<u32>(), 1);
+        assert_eq!(records.iter().map(|record| record.hits).sum::<u32>(), 2);
+        let dealt: f32 = records.iter().map(|record| record.damage_dealt).sum();
+        assert_eq!(dealt, 40.0);
+    }
+
+    #[test]
+    fn test_enemy_killed_on_its_last_step_does_not_leak() {
+        let mut app = combat_app();
+        app.add_systems(
+            Update,
+            move_enemies.after(collision_system).before(process_deaths),
+        );
+        let tower = app.world_mut().spawn(CombatRecord::default()).id();
+        let enemy = spawn_enemy(&mut app, 10.0);
+        app.world_mut()
+            .get_mut::<Enemy>(enemy)
+            .unwrap()
+            .path_progress = calculate_total_path_length();
+        spawn_bullet(&mut app, tower, 25.0);
+        let before = GameState::default();
+
+        app.update();
+
+        let state = app.world().resource::<GameState>();
+        assert_eq!(state.player_health, before.player_health);
+        assert_eq!(state.score, before.score + 10);
+        assert_eq!(state.money, before.money + 5);
+        assert!(app.world().get_entity(enemy).is_err());
+        assert_eq!(app.world().get::<CombatRecord>(tower).unwrap().kills, 1);
+    }
 
-                // Create hit effect
-                commands.spawn((
-                    Mesh2d(meshes.add(Circle::new(8.0))),
-                    MeshMaterial2d(materials.add(Color::srgb(1.0, 0.8, 0.2))),
-                    Transform::from_translation(
-                        enemy_transform.translation + Vec3::new(0.0, 0.0, 2.0),
-                    ),
-                    HitEffect {
-                        timer: Timer::from_seconds(0.2, TimerMode::Once),
-                    },
-                ));
-
-                // Check if enemy is dead
-                if enemy.health <= 0.0 {
-                    // Play death sound and create visual effect
-                    play_console_beep(SoundType::Death);
-                    create_sound_effect_visual(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
-                        enemy_transform.translation,
-                        SoundType::Death,
-                    );
-
-                    // Create explosion effect
-                    for i in 0..6 {
-                        let angle = i as f32 * std::f32::consts::PI * 2.0 / 6.0;
-                        let offset = Vec3::new(angle.cos() * 15.0, angle.sin() * 15.0, 2.0);
-                        commands.spawn((
-                            Mesh2d(meshes.add(Circle::new(3.0))),
-                            MeshMaterial2d(materials.add(Color::srgb(1.0, 0.4, 0.1))),
-                            Transform::from_translation(enemy_transform.translation + offset),
-                            ExplosionParticle {
-                                velocity: Vec2::new(angle.cos() * 80.0, angle.sin() * 80.0),
-                                timer: Timer::from_seconds(0.5, TimerMode::Once),
-                            },
-                        ));
-                    }
-
-                    commands.entity(enemy_entity).despawn();
-                    game_state.score += 10;
-                    game_state.money += 5;
-                }
-
-                break;
+    #[test]
+    fn test_piercing_bullet_counts_one_hit() {
+        let mut app = combat_app();
+        let tower = app
+            .world_mut()
+            .spawn(CombatRecord {
+                shots_fired: 1,
+                ..default()
+            })
+            .id();
+        let enemies: Vec<_> = (0..3).map(|_| spawn_enemy(&mut app, 100.0)).collect();
+        let bullet = spawn_bullet(&mut app, tower, 25.0);
+        app.world_mut().get_mut::<Bullet>(bullet).unwrap().pierce = 2;
+
+        // Enemies stacked on the bullet's path are struck one per update
+        for _ in 0..enemies.len() {
+            app.update();
+        }
+
+        assert!(app.world().get_entity(bullet).is_err());
+        let record = app.world().get::<CombatRecord>(tower).unwrap();
+        assert_eq!(record.damage_dealt, 75.0);
+        assert_eq!(record.hits, 1);
+        assert!(record.hits <= record.shots_fired);
+    }
+
+    #[test]
+    fn test_homing_bullet_fizzles_when_out_of_fuel() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                0.1,
+            )))
+            .insert_resource(GameConfig {
+                obstacles_block_sight: false,
+                ..default()
+            })
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .add_systems(Update, bullet_movement);
+        let tower = app.world_mut().spawn(CombatRecord::default()).id();
+        // Too close to turn into, so the missile circles it
+        spawn_enemy(&mut app, 100.0);
+        let bullet = spawn_bullet(&mut app, tower, 25.0);
+        let mut missile = app.world_mut().get_mut::<Bullet>(bullet).unwrap();
+        missile.kind = ProjectileKind::Homing {
+            turn_rate: 5.0,
+            flight_time: 1.0,
+        };
+        missile.lifetime = Some(Timer::from_seconds(1.0, TimerMode::Once));
+
+        for _ in 0..5 {
+            app.update();
+        }
+        // The fuel burns down on the bullet, not in the projectile's settings
+        let missile = app.world().get::<Bullet>(bullet).unwrap();
+        assert!(missile.lifetime.as_ref().unwrap().elapsed_secs() > 0.0);
+        assert_eq!(
+            missile.kind,
+            ProjectileKind::Homing {
+                turn_rate: 5.0,
+                flight_time: 1.0,
             }
+        );
+
+        for _ in 0..6 {
+            app.update();
         }
+        assert!(app.world().get_entity(bullet).is_err());
+    }
+
+    #[test]
+    fn test_damage_enemy_only_marks_the_killing_blow() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let killer = world.spawn_empty().id();
+        let mut enemy = Enemy {
+            health: 30.0,
+            max_health: 30.0,
+            speed: 50.0,
+            path_progress: 0.0,
+            last_progress: 0.0,
+        };
+
+        let mut queue = bevy::ecs::world::CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        assert_eq!(
+            damage_enemy(&mut commands, entity, &mut enemy, 20.0, None),
+            20.0
+        );
+        assert_eq!(
+            damage_enemy(&mut commands, entity, &mut enemy, 20.0, Some(killer)),
+            10.0
+        );
+        // Already dead, so a later hit lands nothing and can't steal the kill
+        assert_eq!(
+            damage_enemy(&mut commands, entity, &mut enemy, 20.0, None),
+            0.0
+        );
+        queue.apply(&mut world);
+
+        assert_eq!(
+            world.get::<Dead>(entity),
+            Some(&Dead {
+                killer: Some(killer)
+            })
+        );
+    }
+
+    #[test]
+    fn test_steer_limits_turn() {
+        // Target straight up, but only a small turn allowed this frame
+        let direction = steer(Vec2::X, Vec2::Y, 0.1);
+        assert!((direction.to_angle() - 0.1).abs() < 1e-5);
+        assert!((direction.length() - 1.0).abs() < 1e-5);
+
+        // Enough turn to face the target outright
+        let direction = steer(Vec2::X, Vec2::Y * 50.0, 2.0);
+        assert!((direction.to_angle() - FRAC_PI_2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_steer_without_target_direction() {
+        assert_eq!(steer(Vec2::X, Vec2::ZERO, 1.0), Vec2::X);
+    }
+
+    fn stats(crit_chance: f32, damage_spread: f32) -> EffectiveStats {
+        EffectiveStats {
+            crit_chance,
+            crit_multiplier: 2.0,
+            damage_spread,
+            ..EffectiveStats::compute(&TowerKind::Gun.tower(), [])
+        }
+    }
+
+    #[test]
+    fn test_roll_damage_within_spread() {
+        let mut rng = GameRng::new(7);
+        let spread = stats(0.0, 0.2);
+
+        for _ in 0..1000 {
+            let (damage, critical) = roll_damage(&spread, &mut rng);
+            assert!(!critical);
+            assert!((20.0 - 1e-3..=30.0 + 1e-3).contains(&damage));
+        }
+
+        let (damage, _) = roll_damage(&stats(0.0, 0.0), &mut rng);
+        assert_eq!(damage, 25.0);
+    }
+
+    #[test]
+    fn test_roll_damage_crits_at_about_the_chance() {
+        let mut rng = GameRng::new(7);
+        let stats = stats(0.25, 0.0);
+
+        let rolls: Vec<_> = (0..10_000).map(|_| roll_damage(&stats, &mut rng)).collect();
+        let crits = rolls.iter().filter(|(_, critical)| *critical).count();
+        assert!((2_200..2_800).contains(&crits));
+        assert!(rolls
+            .iter()
+            .all(|(damage, critical)| *damage == if *critical { 50.0 } else { 25.0 }));
+    }
+
+    #[test]
+    fn test_roll_damage_is_reproducible() {
+        let stats = stats(0.25, 0.2);
+        let mut a = GameRng::new(99);
+        let mut b = GameRng::new(99);
+
+        for _ in 0..100 {
+            assert_eq!(roll_damage(&stats, &mut a), roll_damage(&stats, &mut b));
+        }
+    }
+
+    #[test]
+    fn test_splash_falloff() {
+        assert_eq!(splash_falloff(0.0, 40.0), 1.0);
+        assert!((splash_falloff(20.0, 40.0) - 0.65).abs() < 1e-5);
+        assert!((splash_falloff(40.0, 40.0) - SPLASH_EDGE_DAMAGE).abs() < 1e-5);
+        assert_eq!(splash_falloff(41.0, 40.0), 0.0);
+        assert_eq!(splash_falloff(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_retarget_closest_in_range() {
+        let enemies = [
+            (Entity::from_raw(1), Vec2::new(100.0, 0.0)),
+            (Entity::from_raw(2), Vec2::new(0.0, 40.0)),
+            (Entity::from_raw(3), Vec2::new(500.0, 0.0)),
+        ];
+
+        assert_eq!(
+            retarget(Vec2::ZERO, enemies, 120.0),
+            Some(Entity::from_raw(2))
+        );
+        assert_eq!(
+            retarget(Vec2::new(450.0, 0.0), enemies, 120.0),
+            Some(Entity::from_raw(3))
+        );
+        assert_eq!(retarget(Vec2::new(0.0, -300.0), enemies, 120.0), None);
     }
 }
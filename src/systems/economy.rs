@@ -0,0 +1,74 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+/// Total paid out by farms at the end of a wave, limited to `cap`
+pub fn total_farm_income(incomes: impl IntoIterator<Item = i32>, cap: i32) -> i32 {
+    incomes.into_iter().sum::<i32>().min(cap)
+}
+
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
+pub fn farm_income(
+    mut wave_completed: EventReader<WaveCompleted>,
+    tower_query: Query<(&Tower, &Transform)>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut game_state: ResMut<GameState>,
+    mut status: ResMut<StatusMessage>,
+    config: Res<GameConfig>,
+) {
+    for event in wave_completed.read() {
+        let farms: Vec<_> = tower_query
+            .iter()
+            .filter(|(tower, _)| tower.kind.income(tower.level) > 0)
+            .collect();
+        if farms.is_empty() {
+            continue;
+        }
+
+        let income = total_farm_income(
+            farms
+                .iter()
+                .map(|(tower, _)| tower.kind.income(tower.level)),
+            config.farm_income_cap,
+        );
+        game_state.money += income;
+        status.show(format!(
+            "Wave {} complete: farms made ${income}",
+            event.wave
+        ));
+
+        // Green flash over each farm that paid out
+        for (_, transform) in farms {
+            commands.spawn((
+                Mesh2d(meshes.add(Circle::new(10.0))),
+                MeshMaterial2d(materials.add(Color::srgba(0.4, 1.0, 0.3, 0.7))),
+                Transform::from_translation(transform.translation + Vec3::new(0.0, 0.0, 2.0)),
+                HitEffect {
+                    timer: Timer::from_seconds(0.5, TimerMode::Once),
+                },
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_farm_income_under_cap() {
+        assert_eq!(total_farm_income([15, 25], 80), 40);
+    }
+
+    #[test]
+    fn test_total_farm_income_capped() {
+        assert_eq!(total_farm_income([35, 35, 35], 80), 80);
+    }
+
+    #[test]
+    fn test_total_farm_income_without_farms() {
+        assert_eq!(total_farm_income([], 80), 0);
+    }
+}
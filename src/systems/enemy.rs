@@ -13,29 +13,76 @@ pub fn spawn_enemies(
 
     if wave_timer.timer.just_finished() {
         let start_pos = Vec3::new(ENEMY_PATH[0].0, ENEMY_PATH[0].1, 1.0);
+        let stealthy = is_stealth_spawn(wave_timer.wave, wave_timer.spawned_this_wave);
+        let color = if stealthy {
+            Color::srgba(1.0, 0.3, 0.3, 0.35)
+        } else {
+            Color::srgb(1.0, 0.3, 0.3)
+        };
 
-        commands.spawn((
+        let mut enemy = commands.spawn((
             Mesh2d(meshes.add(Circle::new(12.0))),
-            MeshMaterial2d(materials.add(Color::srgb(1.0, 0.3, 0.3))),
+            MeshMaterial2d(materials.add(color)),
             Transform::from_translation(start_pos),
             Enemy {
                 health: 100.0,
                 max_health: 100.0,
                 speed: 50.0,
                 path_progress: 0.0,
+                last_progress: 0.0,
             },
+            WaveMember(wave_timer.wave),
         ));
+
+        if stealthy {
+            enemy.insert(Stealth);
+        }
+
+        wave_timer.spawned_this_wave += 1;
+        if wave_timer.spawned_this_wave >= ENEMIES_PER_WAVE {
+            wave_timer.wave += 1;
+            wave_timer.spawned_this_wave = 0;
+        }
+    }
+}
+
+/// End each wave once all of its enemies have spawned and none are left
+/// alive, whether they were killed or got through
+pub fn detect_wave_completion(
+    enemy_query: Query<(&Enemy, &WaveMember), Without<Dead>>,
+    mut wave_timer: ResMut<WaveTimer>,
+    mut wave_completed: EventWriter<WaveCompleted>,
+) {
+    // Waves before the current one have finished spawning
+    while wave_timer.waves_completed + 1 < wave_timer.wave {
+        let wave = wave_timer.waves_completed + 1;
+        let remaining = enemy_query
+            .iter()
+            .any(|(enemy, member)| member.0 == wave && enemy.health > 0.0);
+        if remaining {
+            break;
+        }
+
+        wave_completed.send(WaveCompleted { wave });
+        wave_timer.waves_completed = wave;
     }
 }
 
+/// Walk enemies along the path. Runs after everything that deals damage, so an
+/// enemy killed on its last step is paid out as a kill instead of costing a life.
 pub fn move_enemies(
-    mut enemy_query: Query<(Entity, &mut Transform, &mut Enemy)>,
+    mut enemy_query: Query<(Entity, &mut Transform, &mut Enemy, Option<&Slowed>), Without<Dead>>,
     time: Res<Time>,
     mut game_state: ResMut<GameState>,
     mut commands: Commands,
 ) {
-    for (entity, mut transform, mut enemy) in enemy_query.iter_mut() {
-        enemy.path_progress += enemy.speed * time.delta_secs();
+    for (entity, mut transform, mut enemy, slowed) in enemy_query.iter_mut() {
+        if enemy.health <= 0.0 {
+            continue;
+        }
+
+        enemy.last_progress = enemy.path_progress;
+        enemy.path_progress += enemy.current_speed(slowed) * time.delta_secs();
 
         let total_path_length = calculate_total_path_length();
         let progress_ratio = enemy.path_progress / total_path_length;
@@ -53,15 +100,12 @@ pub fn move_enemies(
     }
 }
 
-pub fn cleanup_dead_entities(enemy_query: Query<(Entity, &Enemy)>, mut commands: Commands) {
-    for (entity, enemy) in enemy_query.iter() {
-        if enemy.health <= 0.0 {
-            commands.entity(entity).despawn();
-        }
-    }
+/// From the second wave on, every fourth enemy is stealthed
+fn is_stealth_spawn(wave: u32, spawned_this_wave: u32) -> bool {
+    wave >= 2 && spawned_this_wave % 4 == 3
 }
 
-fn calculate_total_path_length() -> f32 {
+pub fn calculate_total_path_length() -> f32 {
     let mut total_length = 0.0;
     for i in 0..ENEMY_PATH.len() - 1 {
         let start = Vec2::new(ENEMY_PATH[i].0, ENEMY_PATH[i].1);
@@ -71,7 +115,7 @@ fn calculate_total_path_length() -> f32 {
     total_length
 }
 
-fn calculate_position_on_path(progress: f32) -> Vec3 {
+pub fn calculate_position_on_path(progress: f32) -> Vec3 {
     let mut current_progress = progress;
 
     for i in 0..ENEMY_PATH.len() - 1 {
@@ -164,6 +208,59 @@ mod tests {
         assert_eq!(beyond_pos.z, 1.0);
     }
 
+    fn completed_waves(app: &mut App) -> Vec<u32> {
+        app.world_mut()
+            .resource_mut::<Events<WaveCompleted>>()
+            .drain()
+            .map(|event| event.wave)
+            .collect()
+    }
+
+    #[test]
+    fn test_wave_completes_once_its_enemies_are_gone() {
+        let mut app = App::new();
+        app.add_event::<WaveCompleted>()
+            .insert_resource(WaveTimer {
+                wave: 2,
+                ..default()
+            })
+            .add_systems(Update, detect_wave_completion);
+        let enemy = || Enemy {
+            health: 100.0,
+            max_health: 100.0,
+            speed: 50.0,
+            path_progress: 0.0,
+            last_progress: 0.0,
+        };
+        let last = app.world_mut().spawn((enemy(), WaveMember(1))).id();
+        app.world_mut().spawn((enemy(), WaveMember(2)));
+
+        // The last enemy of the first wave is still walking
+        app.update();
+        assert!(completed_waves(&mut app).is_empty());
+
+        app.world_mut().despawn(last);
+        app.update();
+        assert_eq!(completed_waves(&mut app), vec![1]);
+
+        // Sent only once, and not for the wave that's still spawning
+        app.update();
+        assert!(completed_waves(&mut app).is_empty());
+        assert_eq!(app.world().resource::<WaveTimer>().waves_completed, 1);
+    }
+
+    #[test]
+    fn test_stealth_spawns() {
+        // None in the first wave
+        assert!((0..ENEMIES_PER_WAVE).all(|spawned| !is_stealth_spawn(1, spawned)));
+
+        // Some, but not all, in later waves
+        let stealthed = (0..ENEMIES_PER_WAVE)
+            .filter(|spawned| is_stealth_spawn(2, *spawned))
+            .count();
+        assert!(stealthed > 0 && stealthed < ENEMIES_PER_WAVE as usize);
+    }
+
     #[test]
     fn test_enemy_path_segments() {
         // Test that each segment in the path makes sense
@@ -194,6 +291,7 @@ mod tests {
             max_health: 100.0,
             speed: 50.0,
             path_progress: 0.0,
+            last_progress: 0.0,
         };
 
         assert!(enemy.health > 0.0);
@@ -0,0 +1,129 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+/// Run condition for everything that should stop once the player has lost
+pub fn game_running(game_state: Res<GameState>) -> bool {
+    game_state.player_health > 0
+}
+
+/// One line per tower, best damage dealers first
+pub fn tower_breakdown(records: &[(TowerKind, CombatRecord)]) -> Vec<String> {
+    let mut sorted: Vec<_> = records.iter().collect();
+    sorted.sort_by(|(_, a), (_, b)| b.damage_dealt.total_cmp(&a.damage_dealt));
+
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(index, (kind, record))| {
+            format!(
+                "{}. {}: {:.0} damage, {} kills, {:.0}% hit rate, {:.1} DPS",
+                index + 1,
+                kind.name(),
+                record.damage_dealt,
+                record.kills,
+                record.hit_rate() * 100.0,
+                record.dps()
+            )
+        })
+        .collect()
+}
+
+/// Once the player runs out of health, cover the arena with the final score
+/// and how each tower performed, including ones that were sold
+pub fn show_game_over(
+    game_state: Res<GameState>,
+    wave_timer: Res<WaveTimer>,
+    history: Res<CombatHistory>,
+    tower_query: Query<(&Tower, &CombatRecord)>,
+    screen_query: Query<(), With<GameOverScreen>>,
+    mut commands: Commands,
+) {
+    if game_state.player_health > 0 || !screen_query.is_empty() {
+        return;
+    }
+
+    let mut records = history.0.clone();
+    records.extend(
+        tower_query
+            .iter()
+            .filter(|(tower, _)| tower.kind.attacks())
+            .map(|(tower, record)| (tower.kind, record.clone())),
+    );
+
+    let mut lines = vec![
+        "Game Over".to_string(),
+        format!(
+            "Score: {} | Reached wave {}",
+            game_state.score, wave_timer.wave
+        ),
+        String::new(),
+    ];
+    let breakdown = tower_breakdown(&records);
+    if breakdown.is_empty() {
+        lines.push("No towers fired a shot".to_string());
+    } else {
+        lines.push("Tower breakdown".to_string());
+        lines.extend(breakdown);
+    }
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+            GlobalZIndex(10),
+            GameOverScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(lines.join("\n")),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(damage_dealt: f32, kills: u32) -> CombatRecord {
+        CombatRecord {
+            kills,
+            damage_dealt,
+            shots_fired: 10,
+            hits: 5,
+            time_active: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_tower_breakdown_sorted_by_damage() {
+        let records = [
+            (TowerKind::Gun, record(100.0, 1)),
+            (TowerKind::Sniper, record(900.0, 9)),
+            (TowerKind::Rapid, record(400.0, 3)),
+        ];
+        let lines = tower_breakdown(&records);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("1. Sniper: 900 damage, 9 kills, 50% hit rate, 90.0 DPS"));
+        assert!(lines[1].starts_with("2. Rapid"));
+        assert!(lines[2].starts_with("3. Gun"));
+    }
+
+    #[test]
+    fn test_tower_breakdown_empty() {
+        assert!(tower_breakdown(&[]).is_empty());
+    }
+}
@@ -0,0 +1,398 @@
+use super::placement::cursor_world_position;
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+const HERO_BULLET_SPEED: f32 = 350.0;
+/// How close an enemy has to be to hurt the hero
+const CONTACT_RANGE: f32 = 20.0;
+/// Damage per second each touching enemy does to the hero
+const CONTACT_DAMAGE: f32 = 15.0;
+const REGENERATION_PER_SECOND: f32 = 4.0;
+/// Half the hero's width, so it stops at the edge of scenery
+const HERO_RADIUS: f32 = 10.0;
+
+/// Enemies the hero can shoot at, kept apart from the hero's own transform
+type EnemyFilter = (With<Enemy>, Without<Hero>);
+
+/// The enemies the hero shoots at: the closest ones in range, one per shot,
+/// or two with Multishot
+pub fn hero_targets(origin: Vec2, enemies: &[(Entity, Vec2)], count: usize) -> Vec<(Entity, Vec2)> {
+    let mut in_range: Vec<_> = enemies
+        .iter()
+        .copied()
+        .filter(|(_, position)| origin.distance(*position) <= Hero::RANGE)
+        .collect();
+    in_range.sort_by(|(_, a), (_, b)| origin.distance(*a).total_cmp(&origin.distance(*b)));
+    in_range.truncate(count);
+    in_range
+}
+
+/// Whether the hero can stand at `position`
+pub fn hero_can_walk(position: Vec2, config: &GameConfig) -> bool {
+    config.arena_bounds.contains(position)
+        && !config
+            .blocked_zones
+            .iter()
+            .any(|zone| zone.contains(position))
+        && !config
+            .obstacles
+            .iter()
+            .any(|obstacle| obstacle.contains(position, HERO_RADIUS))
+}
+
+/// Right-clicking the arena sends the hero there
+pub fn handle_hero_orders(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    interaction_query: Query<&Interaction>,
+    config: Res<GameConfig>,
+    mut hero_query: Query<&mut Hero>,
+) {
+    if !mouse_button.just_pressed(MouseButton::Right) {
+        return;
+    }
+
+    if interaction_query.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+
+    let Some(world_pos) = cursor_world_position(&windows, &camera_query) else {
+        return;
+    };
+
+    for mut hero in hero_query.iter_mut() {
+        if hero.is_alive() {
+            hero.destination =
+                Some(world_pos.clamp(config.arena_bounds.min, config.arena_bounds.max));
+        }
+    }
+}
+
+/// Walk toward the ordered destination, stopping short of anything in the way
+pub fn move_hero(
+    mut hero_query: Query<(&mut Hero, &mut Transform)>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    for (mut hero, mut transform) in hero_query.iter_mut() {
+        let Some(destination) = hero.destination.filter(|_| hero.is_alive()) else {
+            continue;
+        };
+
+        let position = transform.translation.truncate();
+        let step = Hero::SPEED * time.delta_secs();
+        let next = if position.distance(destination) <= step {
+            hero.destination = None;
+            destination
+        } else {
+            position + (destination - position).normalize() * step
+        };
+
+        if hero_can_walk(next, &config) {
+            transform.translation = next.extend(transform.translation.z);
+        } else {
+            hero.destination = None;
+        }
+    }
+}
+
+/// Shoot at the closest enemies in range, using the same bullets as towers
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
+pub fn hero_attack(
+    mut hero_query: Query<(Entity, &Transform, &Hero, &mut Reload, &mut CombatRecord)>,
+    enemy_query: Query<(Entity, &Transform, &Enemy, Has<Stealth>), EnemyFilter>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    for (hero_entity, transform, hero, mut reload, mut record) in hero_query.iter_mut() {
+        if !hero.is_alive() {
+            reload.tick(time.delta_secs(), 1.0 / Hero::FIRE_RATE, false);
+            continue;
+        }
+
+        // Only kills and damage should wake up levelling, not time passing
+        record.bypass_change_detection().time_active += time.delta_secs();
+        let origin = transform.translation.truncate();
+
+        let enemies: Vec<_> = enemy_query
+            .iter()
+            .filter(|(_, _, enemy, stealth)| {
+                enemy.health > 0.0 && (!stealth || hero.has_skill(HeroSkill::TrueSight))
+            })
+            .map(|(entity, transform, _, _)| (entity, transform.translation.truncate()))
+            .filter(|(_, position)| !config.sight_blocked(origin, *position))
+            .collect();
+        let count = if hero.has_skill(HeroSkill::Multishot) {
+            2
+        } else {
+            1
+        };
+        let targets = hero_targets(origin, &enemies, count);
+
+        let shots = reload.tick(
+            time.delta_secs(),
+            1.0 / Hero::FIRE_RATE,
+            !targets.is_empty(),
+        );
+
+        for _ in 0..shots {
+            for (_, target) in &targets {
+                record.shots_fired += 1;
+                commands.spawn((
+                    Mesh2d(meshes.add(Circle::new(3.0))),
+                    MeshMaterial2d(materials.add(Color::srgb(1.0, 0.6, 0.2))),
+                    Transform::from_translation(transform.translation + Vec3::Z),
+                    Bullet::new(
+                        hero.damage(),
+                        HERO_BULLET_SPEED,
+                        (*target - origin).normalize_or_zero(),
+                        hero_entity,
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Enemies walking into the hero wear it down. A fallen hero disappears until
+/// it respawns.
+pub fn hero_contact_damage(
+    mut hero_query: Query<(&mut Hero, &Transform, &mut Visibility)>,
+    enemy_query: Query<&Transform, EnemyFilter>,
+    mut status: ResMut<StatusMessage>,
+    time: Res<Time>,
+) {
+    for (mut hero, transform, mut visibility) in hero_query.iter_mut() {
+        if !hero.is_alive() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let touching = enemy_query
+            .iter()
+            .filter(|enemy| enemy.translation.truncate().distance(position) <= CONTACT_RANGE)
+            .count();
+        hero.health -= CONTACT_DAMAGE * touching as f32 * time.delta_secs();
+
+        if hero.has_skill(HeroSkill::Regeneration) {
+            hero.health = (hero.health + REGENERATION_PER_SECOND * time.delta_secs())
+                .min(Hero::max_health(hero.level));
+        }
+
+        if hero.health <= 0.0 {
+            hero.health = 0.0;
+            hero.destination = None;
+            hero.respawn = Some(Timer::from_seconds(Hero::RESPAWN_SECONDS, TimerMode::Once));
+            *visibility = Visibility::Hidden;
+            status.show("Your hero has fallen");
+        }
+    }
+}
+
+/// Bring a fallen hero back at full health at the spawn point
+pub fn respawn_hero(
+    mut hero_query: Query<(&mut Hero, &mut Transform, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    for (mut hero, mut transform, mut visibility) in hero_query.iter_mut() {
+        let Some(timer) = hero.respawn.as_mut() else {
+            continue;
+        };
+
+        timer.tick(time.delta());
+        if timer.finished() {
+            hero.respawn = None;
+            hero.health = Hero::max_health(hero.level);
+            transform.translation = HERO_SPAWN.extend(transform.translation.z);
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+/// Level the hero up as it gains experience, healing it to its new maximum
+pub fn level_up_hero(
+    mut hero_query: Query<(&mut Hero, &CombatRecord), Changed<CombatRecord>>,
+    mut status: ResMut<StatusMessage>,
+) {
+    for (mut hero, record) in hero_query.iter_mut() {
+        let level = Hero::level_for(record);
+        if level <= hero.level {
+            continue;
+        }
+
+        hero.level = level;
+        if hero.is_alive() {
+            hero.health = Hero::max_health(level);
+        }
+
+        let unlocked: Vec<_> = HeroSkill::ALL
+            .iter()
+            .filter(|skill| skill.unlock_level() == level)
+            .map(|skill| skill.name())
+            .collect();
+        status.show(if unlocked.is_empty() {
+            format!("Hero reached level {}", level)
+        } else {
+            format!(
+                "Hero reached level {}: {} unlocked",
+                level,
+                unlocked.join(", ")
+            )
+        });
+    }
+}
+
+/// Health bar over the hero and a line to where it's heading
+pub fn draw_hero(hero_query: Query<(&Hero, &Transform)>, mut gizmos: Gizmos) {
+    for (hero, transform) in hero_query.iter() {
+        if !hero.is_alive() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let bar_start = position + Vec2::new(-12.0, 18.0);
+        let health = hero.health / Hero::max_health(hero.level);
+        gizmos.line_2d(
+            bar_start,
+            bar_start + Vec2::X * 24.0,
+            Color::srgba(0.3, 0.0, 0.0, 0.8),
+        );
+        gizmos.line_2d(
+            bar_start,
+            bar_start + Vec2::X * 24.0 * health,
+            Color::srgb(0.2, 1.0, 0.3),
+        );
+
+        if let Some(destination) = hero.destination {
+            gizmos.line_2d(position, destination, Color::srgba(1.0, 0.6, 0.2, 0.4));
+            gizmos.circle_2d(destination, 5.0, Color::srgb(1.0, 0.6, 0.2));
+        }
+    }
+}
+
+pub fn update_hero_text(
+    hero_query: Query<&Hero>,
+    mut text_query: Query<&mut Text, With<HeroText>>,
+) {
+    let (Ok(hero), Ok(mut text)) = (hero_query.get_single(), text_query.get_single_mut()) else {
+        return;
+    };
+
+    let value = match &hero.respawn {
+        Some(timer) => format!("Hero: respawning in {:.0}s", timer.remaining_secs().ceil()),
+        None => {
+            let skills: Vec<_> = hero.skills().map(|skill| skill.name()).collect();
+            format!(
+                "Hero level {}: {:.0}/{:.0} HP{}",
+                hero.level,
+                hero.health.ceil(),
+                Hero::max_health(hero.level),
+                if skills.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", skills.join(", "))
+                }
+            )
+        }
+    };
+
+    if **text != value {
+        **text = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_hero_targets_closest_in_range() {
+        let enemies = [
+            (Entity::from_raw(1), Vec2::new(60.0, 0.0)),
+            (Entity::from_raw(2), Vec2::new(20.0, 0.0)),
+            (Entity::from_raw(3), Vec2::new(40.0, 0.0)),
+            (Entity::from_raw(4), Vec2::new(500.0, 0.0)),
+        ];
+
+        let targets = hero_targets(Vec2::ZERO, &enemies, 1);
+        assert_eq!(targets, vec![enemies[1]]);
+
+        let targets = hero_targets(Vec2::ZERO, &enemies, 2);
+        assert_eq!(targets, vec![enemies[1], enemies[2]]);
+    }
+
+    #[test]
+    fn test_hero_targets_none_in_range() {
+        let enemies = [(Entity::from_raw(1), Vec2::new(500.0, 0.0))];
+        assert!(hero_targets(Vec2::ZERO, &enemies, 2).is_empty());
+    }
+
+    #[test]
+    fn test_hero_can_walk() {
+        let config = GameConfig::default();
+
+        assert!(hero_can_walk(HERO_SPAWN, &config));
+        // The enemy path is fine to stand on, scenery and water aren't
+        assert!(hero_can_walk(Vec2::new(0.0, -100.0), &config));
+        assert!(!hero_can_walk(Vec2::new(-60.0, 60.0), &config));
+        assert!(!hero_can_walk(config.blocked_zones[0].center(), &config));
+        assert!(!hero_can_walk(Vec2::new(0.0, 500.0), &config));
+    }
+
+    #[derive(Resource, Default)]
+    struct RecordChanges(u32);
+
+    fn count_record_changes(
+        record_query: Query<(), Changed<CombatRecord>>,
+        mut changes: ResMut<RecordChanges>,
+    ) {
+        changes.0 += record_query.iter().count() as u32;
+    }
+
+    #[test]
+    fn test_idle_hero_leaves_record_unchanged() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                0.1,
+            )))
+            .init_resource::<GameConfig>()
+            .init_resource::<RecordChanges>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .add_systems(
+                Update,
+                (hero_attack, count_record_changes.after(hero_attack)),
+            );
+        let hero = app
+            .world_mut()
+            .spawn((
+                Hero {
+                    health: Hero::max_health(1),
+                    level: 1,
+                    destination: None,
+                    respawn: None,
+                },
+                Transform::from_translation(HERO_SPAWN.extend(1.0)),
+                Reload::default(),
+                CombatRecord::default(),
+            ))
+            .id();
+
+        for _ in 0..3 {
+            app.update();
+        }
+
+        // Only spawning the hero counts; standing around with nothing to shoot
+        // doesn't
+        assert_eq!(app.world().resource::<RecordChanges>().0, 1);
+        assert!(app.world().get::<CombatRecord>(hero).unwrap().time_active > 0.0);
+    }
+}
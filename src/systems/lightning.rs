@@ -0,0 +1,270 @@
+use super::combat::{damage_enemy, roll_damage, spawn_hit_effect};
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+/// Share of the damage each jump keeps from the one before
+const CHAIN_FALLOFF: f32 = 0.7;
+/// How far the kinks in a bolt stray from a straight line
+const BOLT_JAGGEDNESS: f32 = 7.0;
+const BOLT_SEGMENTS: usize = 6;
+
+/// Everything chain lightning needs to know about an enemy
+type ChainVictim<'a> = (
+    Entity,
+    &'a mut Enemy,
+    &'a Transform,
+    Has<Slowed>,
+    Has<Stealth>,
+);
+
+/// The enemies a strike on `first` goes on to hit: each jump goes to the
+/// closest enemy within `range` of the last one that hasn't been hit yet and
+/// isn't behind scenery, stopping after `jumps` jumps or when nothing is in reach
+pub fn chain_targets(
+    first: (Entity, Vec2),
+    enemies: &[(Entity, Vec2)],
+    jumps: u32,
+    range: f32,
+    config: &GameConfig,
+) -> Vec<(Entity, Vec2)> {
+    let mut chain = vec![first];
+
+    for _ in 0..jumps {
+        let (_, last) = chain[chain.len() - 1];
+        let next = enemies
+            .iter()
+            .filter(|(entity, _)| chain.iter().all(|(hit, _)| hit != entity))
+            .filter(|(_, position)| last.distance(*position) <= range)
+            .filter(|(_, position)| !config.sight_blocked(last, *position))
+            .min_by(|(_, a), (_, b)| last.distance(*a).total_cmp(&last.distance(*b)));
+
+        match next {
+            Some(&next) => chain.push(next),
+            None => break,
+        }
+    }
+
+    chain
+}
+
+/// Damage done by the `hop`th strike of a chain, the first being hop 0
+pub fn chain_damage(damage: f32, hop: usize) -> f32 {
+    damage * CHAIN_FALLOFF.powi(hop as i32)
+}
+
+/// Points of a zigzag bolt from `from` to `to`. The kinks alternate sides and
+/// vary in size, and depend only on the end points so a bolt doesn't flicker.
+pub fn jagged_path(from: Vec2, to: Vec2) -> Vec<Vec2> {
+    let side = (to - from).perp().normalize_or_zero();
+    let seed = (from.x + from.y * 3.0 + to.x * 7.0 + to.y * 11.0).abs() as usize;
+
+    let mut points = vec![from];
+    for i in 1..BOLT_SEGMENTS {
+        let along = from.lerp(to, i as f32 / BOLT_SEGMENTS as f32);
+        let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+        let size = 0.4 + ((seed + i * 7) % 7) as f32 / 10.0;
+        points.push(along + side * sign * size * BOLT_JAGGEDNESS);
+    }
+    points.push(to);
+    points
+}
+
+/// Resolve chain lightning fired this frame: hit the target, jump on to nearby
+/// enemies for less damage each time, and leave a bolt along the way
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
+pub fn chain_lightning(
+    mut strikes: EventReader<LightningStrike>,
+    mut enemy_query: Query<ChainVictim>,
+    mut record_query: Query<&mut CombatRecord>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut rng: ResMut<GameRng>,
+    config: Res<GameConfig>,
+) {
+    for strike in strikes.read() {
+        let enemies: Vec<_> = enemy_query
+            .iter()
+            .filter(|(_, enemy, _, _, stealth)| {
+                enemy.health > 0.0 && (!stealth || strike.stats.reveals_stealth)
+            })
+            .map(|(entity, _, transform, _, _)| (entity, transform.translation.truncate()))
+            .collect();
+
+        // The target may already have been finished off this frame
+        let Some(&first) = enemies.iter().find(|(entity, _)| *entity == strike.target) else {
+            continue;
+        };
+
+        let chain = chain_targets(first, &enemies, strike.jumps, strike.range, &config);
+        // One roll for the whole strike, so a critical bolt is critical all along
+        let (strike_damage, critical) = roll_damage(&strike.stats, &mut rng);
+
+        let mut points = vec![strike.origin];
+        points.extend(chain.iter().map(|(_, position)| *position));
+        let bolt = points
+            .windows(2)
+            .flat_map(|pair| jagged_path(pair[0], pair[1]))
+            .collect();
+        commands.spawn(LightningBolt {
+            points: bolt,
+            timer: Timer::from_seconds(0.25, TimerMode::Once),
+        });
+
+        // The source may have been sold since it fired
+        let mut record = record_query.get_mut(strike.source).ok();
+        if let Some(record) = record.as_mut() {
+            record.hits += 1;
+        }
+
+        for (hop, (victim, _)) in chain.into_iter().enumerate() {
+            let Ok((entity, mut enemy, transform, slowed, _)) = enemy_query.get_mut(victim) else {
+                continue;
+            };
+
+            let mut damage = chain_damage(strike_damage, hop);
+            if slowed {
+                damage *= 1.0 + strike.stats.slowed_damage;
+            }
+            let dealt = damage_enemy(
+                &mut commands,
+                entity,
+                &mut enemy,
+                damage,
+                Some(strike.source),
+            );
+
+            if let Some(record) = record.as_mut() {
+                record.damage_dealt += dealt;
+            }
+
+            spawn_hit_effect(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                transform.translation,
+                critical,
+            );
+        }
+    }
+}
+
+/// Draw lightning bolts, fading them out and removing them once they're gone
+pub fn draw_lightning(
+    mut bolt_query: Query<(Entity, &mut LightningBolt)>,
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+) {
+    for (entity, mut bolt) in bolt_query.iter_mut() {
+        bolt.timer.tick(time.delta());
+        if bolt.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let alpha = 1.0 - bolt.timer.fraction();
+        gizmos.linestrip_2d(
+            bolt.points.iter().copied(),
+            Color::srgba(0.7, 0.95, 1.0, alpha),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enemy(index: u32, x: f32) -> (Entity, Vec2) {
+        (Entity::from_raw(index), Vec2::new(x, 0.0))
+    }
+
+    /// An arena with no scenery in the way
+    fn open() -> GameConfig {
+        GameConfig {
+            obstacles: Vec::new(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_chain_jumps_to_closest_unhit_enemies() {
+        let enemies = [
+            enemy(1, 0.0),
+            enemy(2, 50.0),
+            enemy(3, 30.0),
+            enemy(4, 90.0),
+        ];
+
+        let chain = chain_targets(enemies[0], &enemies, 3, 70.0, &open());
+        let order: Vec<_> = chain.iter().map(|(entity, _)| entity.index()).collect();
+        assert_eq!(order, vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn test_chain_never_hits_the_same_enemy_twice() {
+        // Two enemies close together can't bounce back and forth
+        let enemies = [enemy(1, 0.0), enemy(2, 10.0)];
+
+        let chain = chain_targets(enemies[0], &enemies, 5, 70.0, &open());
+        assert_eq!(chain.len(), 2);
+    }
+
+    #[test]
+    fn test_chain_stops_when_nothing_in_reach() {
+        let enemies = [enemy(1, 0.0), enemy(2, 50.0), enemy(3, 200.0)];
+
+        let chain = chain_targets(enemies[0], &enemies, 3, 70.0, &open());
+        assert_eq!(chain.len(), 2);
+
+        let chain = chain_targets(enemies[0], &enemies, 0, 70.0, &open());
+        assert_eq!(chain, vec![enemies[0]]);
+    }
+
+    #[test]
+    fn test_chain_doesnt_jump_through_walls() {
+        let enemies = [enemy(1, 0.0), enemy(2, 30.0), enemy(3, 50.0)];
+        let walled = GameConfig {
+            obstacles: vec![Obstacle::Wall(Rect::new(35.0, -20.0, 40.0, 20.0))],
+            ..default()
+        };
+
+        let chain = chain_targets(enemies[0], &enemies, 3, 70.0, &walled);
+        assert_eq!(chain, vec![enemies[0], enemies[1]]);
+
+        let chain = chain_targets(
+            enemies[0],
+            &enemies,
+            3,
+            70.0,
+            &GameConfig {
+                obstacles_block_sight: false,
+                ..walled
+            },
+        );
+        assert_eq!(chain.len(), 3);
+    }
+
+    #[test]
+    fn test_chain_damage_decays() {
+        assert_eq!(chain_damage(20.0, 0), 20.0);
+        assert!((chain_damage(20.0, 1) - 14.0).abs() < 1e-4);
+        assert!(chain_damage(20.0, 3) < chain_damage(20.0, 2));
+    }
+
+    #[test]
+    fn test_jagged_path_joins_end_points() {
+        let from = Vec2::new(-40.0, 10.0);
+        let to = Vec2::new(60.0, 30.0);
+        let points = jagged_path(from, to);
+
+        assert_eq!(points.len(), BOLT_SEGMENTS + 1);
+        assert_eq!(points[0], from);
+        assert_eq!(points[BOLT_SEGMENTS], to);
+        // Kinks stay close to the straight line
+        assert!(points.iter().all(|point| point.distance(from.lerp(to, 0.5))
+            <= from.distance(to) / 2.0 + BOLT_JAGGEDNESS * 1.1));
+        assert_eq!(points, jagged_path(from, to));
+    }
+}
@@ -1,15 +1,40 @@
+pub mod abilities;
+pub mod beam;
 pub mod combat;
+pub mod economy;
 pub mod effects;
 pub mod enemy;
+pub mod game_over;
+pub mod hero;
+pub mod lightning;
+pub mod placement;
+pub mod relocation;
+pub mod selection;
 pub mod setup;
 pub mod sound;
+pub mod stats;
+pub mod synergy;
+pub mod targeting;
 pub mod tower;
+pub mod traps;
 pub mod ui;
 
+pub use abilities::*;
+pub use beam::*;
 pub use combat::*;
+pub use economy::*;
 pub use effects::*;
 pub use enemy::*;
+pub use game_over::*;
+pub use hero::*;
+pub use lightning::*;
+pub use placement::*;
+pub use relocation::*;
+pub use selection::*;
 pub use setup::*;
 pub use sound::*;
+pub use stats::*;
+pub use synergy::*;
 pub use tower::*;
+pub use traps::*;
 pub use ui::*;
@@ -0,0 +1,533 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+const VALID_GHOST_COLOR: Color = Color::srgba(0.3, 1.0, 0.4, 0.5);
+const INVALID_GHOST_COLOR: Color = Color::srgba(1.0, 0.2, 0.2, 0.5);
+
+/// Towers are 24 units square, so this keeps them from overlapping scenery
+const TOWER_HALF_SIZE: f32 = 12.0;
+
+/// Why a tower can't be built at a given spot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    InsufficientFunds,
+    OutOfBounds,
+    OnPath,
+    BlockedTerrain,
+    Obstacle,
+    TooCloseToTower,
+    NotOnPath,
+    TooCloseToTrap,
+}
+
+impl PlacementError {
+    pub fn message(self) -> &'static str {
+        match self {
+            PlacementError::InsufficientFunds => "Not enough money",
+            PlacementError::OutOfBounds => "Can't build outside the arena",
+            PlacementError::OnPath => "Can't build on the enemy path",
+            PlacementError::BlockedTerrain => "Can't build on water",
+            PlacementError::Obstacle => "Can't build on rocks or walls",
+            PlacementError::TooCloseToTower => "Too close to another tower",
+            PlacementError::NotOnPath => "Traps must go on the enemy path",
+            PlacementError::TooCloseToTrap => "Too close to another trap",
+        }
+    }
+}
+
+/// Check a build spot against the map rules. Funds are checked separately as
+/// they depend on what's being built.
+pub fn validate_placement(
+    position: Vec2,
+    other_towers: impl IntoIterator<Item = Vec2>,
+    config: &GameConfig,
+) -> Result<(), PlacementError> {
+    if !config.arena_bounds.contains(position) {
+        return Err(PlacementError::OutOfBounds);
+    }
+
+    if distance_to_path(position) < config.min_path_distance {
+        return Err(PlacementError::OnPath);
+    }
+
+    if config
+        .blocked_zones
+        .iter()
+        .any(|zone| zone.contains(position))
+    {
+        return Err(PlacementError::BlockedTerrain);
+    }
+
+    if config
+        .obstacles
+        .iter()
+        .any(|obstacle| obstacle.contains(position, TOWER_HALF_SIZE))
+    {
+        return Err(PlacementError::Obstacle);
+    }
+
+    if other_towers
+        .into_iter()
+        .any(|tower| position.distance(tower) < config.min_tower_spacing)
+    {
+        return Err(PlacementError::TooCloseToTower);
+    }
+
+    Ok(())
+}
+
+/// Centre of the grid cell containing `position`
+pub fn snap_to_grid(position: Vec2, grid_size: f32) -> Vec2 {
+    ((position / grid_size).floor() + Vec2::splat(0.5)) * grid_size
+}
+
+/// Snap a cursor position to the build grid and check whether a tower
+/// costing `cost` can be built there
+pub fn check_build_spot(
+    cursor: Vec2,
+    money: i32,
+    cost: i32,
+    other_towers: impl IntoIterator<Item = Vec2>,
+    config: &GameConfig,
+) -> (Vec2, Result<(), PlacementError>) {
+    let position = snap_to_grid(cursor, config.grid_size);
+
+    let result = if money < cost {
+        Err(PlacementError::InsufficientFunds)
+    } else {
+        validate_placement(position, other_towers, config)
+    };
+
+    (position, result)
+}
+
+/// Snap a cursor position to the build grid and check whether an already
+/// placed tower can be moved there for `fee`. The tower being moved doesn't
+/// count as being in its own way.
+pub fn check_relocation_spot(
+    cursor: Vec2,
+    money: i32,
+    fee: i32,
+    moving: Entity,
+    towers: impl IntoIterator<Item = (Entity, Vec2)>,
+    config: &GameConfig,
+) -> (Vec2, Result<(), PlacementError>) {
+    let others = towers
+        .into_iter()
+        .filter(|(entity, _)| *entity != moving)
+        .map(|(_, position)| position);
+
+    check_build_spot(cursor, money, fee, others, config)
+}
+
+/// Snap a cursor position onto the enemy path and check whether a trap
+/// costing `cost` can be built there. On success, returns how far along the
+/// path the trap sits.
+pub fn check_trap_spot(
+    cursor: Vec2,
+    money: i32,
+    cost: i32,
+    other_traps: impl IntoIterator<Item = Vec2>,
+    config: &GameConfig,
+) -> (Vec2, Result<f32, PlacementError>) {
+    let (position, progress) = project_onto_path(cursor);
+
+    let result = if money < cost {
+        Err(PlacementError::InsufficientFunds)
+    } else if cursor.distance(position) > config.trap_snap_distance {
+        Err(PlacementError::NotOnPath)
+    } else if other_traps
+        .into_iter()
+        .any(|trap| position.distance(trap) < config.min_trap_spacing)
+    {
+        Err(PlacementError::TooCloseToTrap)
+    } else {
+        Ok(progress)
+    };
+
+    (position, result)
+}
+
+/// Where the cursor is in the world, if it's over the window
+pub fn cursor_world_position(
+    windows: &Query<&Window>,
+    camera_query: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let window = windows.get_single().ok()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    let cursor_pos = window.cursor_position()?;
+
+    camera
+        .viewport_to_world_2d(camera_transform, cursor_pos)
+        .ok()
+}
+
+/// Move the ghost to the snapped cursor position, tinted by whether the
+/// current build choice can be placed there, and draw the range it would cover
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
+pub fn update_placement_ghost(
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    interaction_query: Query<&Interaction>,
+    tower_query: Query<
+        (Entity, &Transform, &Tower, Option<&EffectiveStats>),
+        Without<PlacementGhost>,
+    >,
+    trap_query: Query<&Transform, (With<Trap>, Without<PlacementGhost>)>,
+    mut ghost_query: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &MeshMaterial2d<ColorMaterial>,
+        ),
+        With<PlacementGhost>,
+    >,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut gizmos: Gizmos,
+    game_state: Res<GameState>,
+    build_selection: Res<BuildSelection>,
+    relocating: Res<Relocating>,
+    config: Res<GameConfig>,
+) {
+    let Ok((mut transform, mut visibility, material)) = ghost_query.get_single_mut() else {
+        return;
+    };
+
+    let over_ui = interaction_query.iter().any(|i| *i != Interaction::None);
+    let moving = relocating.0.and_then(|entity| tower_query.get(entity).ok());
+    let cursor = cursor_world_position(&windows, &camera_query).filter(|cursor| {
+        // Hovering a placed tower means a click will select it, not build
+        !over_ui
+            && tower_query.iter().all(|(entity, tower, _, _)| {
+                Some(entity) == relocating.0
+                    || cursor.distance(tower.translation.truncate()) >= 16.0
+            })
+    });
+
+    // A tower being moved takes priority over the build selection
+    let choice = match moving {
+        Some((_, _, tower, _)) => Some(BuildChoice::Tower(tower.kind)),
+        None => build_selection.0,
+    };
+    let (Some(cursor), Some(choice)) = (cursor, choice) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let (position, valid, range) = match choice {
+        BuildChoice::Tower(kind) => {
+            let (position, result) = match moving {
+                Some((entity, _, _, _)) => check_relocation_spot(
+                    cursor,
+                    game_state.money,
+                    config.relocation_fee,
+                    entity,
+                    tower_query
+                        .iter()
+                        .map(|(entity, tower, _, _)| (entity, tower.translation.truncate())),
+                    &config,
+                ),
+                None => check_build_spot(
+                    cursor,
+                    game_state.money,
+                    kind.cost(),
+                    tower_query
+                        .iter()
+                        .map(|(_, tower, _, _)| tower.translation.truncate()),
+                    &config,
+                ),
+            };
+            // A tower being moved shows the range its upgrades and rank give it
+            let range = moving
+                .and_then(|(_, _, _, stats)| stats)
+                .map_or(kind.tower().range, |stats| stats.range);
+            (position, result.is_ok(), range)
+        }
+        BuildChoice::Trap(kind) => {
+            let (position, result) = check_trap_spot(
+                cursor,
+                game_state.money,
+                kind.cost(),
+                trap_query.iter().map(|trap| trap.translation.truncate()),
+                &config,
+            );
+            (position, result.is_ok(), kind.radius())
+        }
+    };
+    let color = if valid {
+        VALID_GHOST_COLOR
+    } else {
+        INVALID_GHOST_COLOR
+    };
+
+    *visibility = Visibility::Inherited;
+    transform.translation = position.extend(transform.translation.z);
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.color = color;
+    }
+    if range > 0.0 {
+        gizmos.circle_2d(position, range, color);
+    }
+}
+
+/// Shortest distance from `point` to any segment of the enemy path
+pub fn distance_to_path(point: Vec2) -> f32 {
+    ENEMY_PATH
+        .windows(2)
+        .map(|segment| {
+            let start = Vec2::new(segment[0].0, segment[0].1);
+            let end = Vec2::new(segment[1].0, segment[1].1);
+            distance_to_segment(point, start, end)
+        })
+        .fold(f32::MAX, f32::min)
+}
+
+/// Closest point on the enemy path to `point`, and how far along the path it is
+pub fn project_onto_path(point: Vec2) -> (Vec2, f32) {
+    let mut best = (Vec2::new(ENEMY_PATH[0].0, ENEMY_PATH[0].1), 0.0);
+    let mut best_distance = f32::MAX;
+    let mut travelled = 0.0;
+
+    for segment in ENEMY_PATH.windows(2) {
+        let start = Vec2::new(segment[0].0, segment[0].1);
+        let end = Vec2::new(segment[1].0, segment[1].1);
+        let length = start.distance(end);
+
+        let t = if length == 0.0 {
+            0.0
+        } else {
+            ((point - start).dot(end - start) / (length * length)).clamp(0.0, 1.0)
+        };
+        let closest = start.lerp(end, t);
+        let distance = point.distance(closest);
+        if distance < best_distance {
+            best_distance = distance;
+            best = (closest, travelled + length * t);
+        }
+
+        travelled += length;
+    }
+
+    best
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_to_segment() {
+        let start = Vec2::new(0.0, 0.0);
+        let end = Vec2::new(100.0, 0.0);
+
+        // Perpendicular to the middle of the segment
+        assert_eq!(distance_to_segment(Vec2::new(50.0, 30.0), start, end), 30.0);
+        // Past the end, so measured to the endpoint
+        assert_eq!(
+            distance_to_segment(Vec2::new(130.0, 40.0), start, end),
+            50.0
+        );
+    }
+
+    #[test]
+    fn test_snap_to_grid() {
+        assert_eq!(
+            snap_to_grid(Vec2::new(5.0, 35.0), 40.0),
+            Vec2::new(20.0, 20.0)
+        );
+        assert_eq!(
+            snap_to_grid(Vec2::new(-5.0, 41.0), 40.0),
+            Vec2::new(-20.0, 60.0)
+        );
+        // Already on a cell centre
+        assert_eq!(
+            snap_to_grid(Vec2::new(-60.0, 100.0), 40.0),
+            Vec2::new(-60.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_check_build_spot() {
+        let config = GameConfig::default();
+
+        let (position, result) = check_build_spot(Vec2::new(-295.0, 105.0), 100, 20, [], &config);
+        assert_eq!(position, Vec2::new(-300.0, 100.0));
+        assert_eq!(result, Ok(()));
+
+        let (_, result) = check_build_spot(Vec2::new(-295.0, 105.0), 10, 20, [], &config);
+        assert_eq!(result, Err(PlacementError::InsufficientFunds));
+    }
+
+    #[test]
+    fn test_relocation_ignores_the_moving_tower() {
+        let config = GameConfig::default();
+        let moving = Entity::from_raw(1);
+        let towers = [
+            (moving, Vec2::new(-300.0, 100.0)),
+            (Entity::from_raw(2), Vec2::new(-300.0, 20.0)),
+        ];
+
+        // Right next to where the tower stands now
+        let (position, result) =
+            check_relocation_spot(Vec2::new(-265.0, 105.0), 100, 15, moving, towers, &config);
+        assert_eq!(position, Vec2::new(-260.0, 100.0));
+        assert_eq!(result, Ok(()));
+
+        // Other towers still get in the way
+        let (_, result) =
+            check_relocation_spot(Vec2::new(-295.0, 25.0), 100, 15, moving, towers, &config);
+        assert_eq!(result, Err(PlacementError::TooCloseToTower));
+
+        let (_, result) =
+            check_relocation_spot(Vec2::new(-265.0, 105.0), 10, 15, moving, towers, &config);
+        assert_eq!(result, Err(PlacementError::InsufficientFunds));
+    }
+
+    #[test]
+    fn test_project_onto_path() {
+        // The first segment runs 200 units right from the start
+        let (position, progress) = project_onto_path(Vec2::new(-300.0, 215.0));
+        assert_eq!(position, Vec2::new(-300.0, 200.0));
+        assert_eq!(progress, 100.0);
+
+        // Second segment runs down from the first bend
+        let (position, progress) = project_onto_path(Vec2::new(-190.0, 100.0));
+        assert!(position.distance(Vec2::new(-200.0, 100.0)) < 0.001);
+        assert!((progress - 300.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_check_trap_spot() {
+        let config = GameConfig::default();
+        let cursor = Vec2::new(0.0, -90.0);
+
+        let (position, result) = check_trap_spot(cursor, 100, 20, [], &config);
+        assert_eq!(position, Vec2::new(0.0, -100.0));
+        assert!(result.is_ok());
+
+        let (_, result) = check_trap_spot(Vec2::new(0.0, 0.0), 100, 20, [], &config);
+        assert_eq!(result, Err(PlacementError::NotOnPath));
+
+        let (_, result) = check_trap_spot(cursor, 100, 20, [Vec2::new(10.0, -100.0)], &config);
+        assert_eq!(result, Err(PlacementError::TooCloseToTrap));
+
+        let (_, result) = check_trap_spot(cursor, 10, 20, [], &config);
+        assert_eq!(result, Err(PlacementError::InsufficientFunds));
+    }
+
+    #[test]
+    fn test_distance_to_path_on_waypoints() {
+        for (x, y) in ENEMY_PATH {
+            assert_eq!(distance_to_path(Vec2::new(x, y)), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_valid_placement() {
+        let config = GameConfig::default();
+
+        assert_eq!(
+            validate_placement(Vec2::new(-300.0, 100.0), [], &config),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_placement_on_path_rejected() {
+        let config = GameConfig::default();
+
+        assert_eq!(
+            validate_placement(Vec2::new(0.0, -100.0), [], &config),
+            Err(PlacementError::OnPath)
+        );
+        assert_eq!(
+            validate_placement(Vec2::new(0.0, -80.0), [], &config),
+            Err(PlacementError::OnPath)
+        );
+    }
+
+    #[test]
+    fn test_placement_out_of_bounds_rejected() {
+        let config = GameConfig::default();
+
+        assert_eq!(
+            validate_placement(Vec2::new(0.0, 500.0), [], &config),
+            Err(PlacementError::OutOfBounds)
+        );
+        assert_eq!(
+            validate_placement(Vec2::new(-700.0, 0.0), [], &config),
+            Err(PlacementError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_placement_on_blocked_terrain_rejected() {
+        let config = GameConfig::default();
+
+        for zone in &config.blocked_zones {
+            assert_eq!(
+                validate_placement(zone.center(), [], &config),
+                Err(PlacementError::BlockedTerrain)
+            );
+        }
+    }
+
+    #[test]
+    fn test_placement_on_obstacle_rejected() {
+        let config = GameConfig::default();
+
+        assert_eq!(
+            validate_placement(Vec2::new(-60.0, 60.0), [], &config),
+            Err(PlacementError::Obstacle)
+        );
+        // Overlapping the edge of the wall is refused too
+        assert_eq!(
+            validate_placement(Vec2::new(100.0, -5.0), [], &config),
+            Err(PlacementError::Obstacle)
+        );
+    }
+
+    #[test]
+    fn test_placement_near_tower_rejected() {
+        let config = GameConfig::default();
+        let position = Vec2::new(-300.0, 100.0);
+
+        assert_eq!(
+            validate_placement(position, [position + Vec2::new(30.0, 0.0)], &config),
+            Err(PlacementError::TooCloseToTower)
+        );
+        assert_eq!(
+            validate_placement(position, [position + Vec2::new(80.0, 0.0)], &config),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_blocked_zones_clear_of_path() {
+        for segment in ENEMY_PATH.windows(2) {
+            let start = Vec2::new(segment[0].0, segment[0].1);
+            let end = Vec2::new(segment[1].0, segment[1].1);
+
+            for step in 0..=20 {
+                let point = start.lerp(end, step as f32 / 20.0);
+                assert!(BLOCKED_ZONES.iter().all(|zone| !zone.contains(point)));
+                // Leave room for enemies to pass
+                assert!(OBSTACLES
+                    .iter()
+                    .all(|obstacle| !obstacle.contains(point, 20.0)));
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use super::placement::{check_relocation_spot, cursor_world_position};
+use super::tower::spawn_refused_marker;
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+/// Label for the move button: the fee, the wait before the tower can be moved
+/// again, or a reminder that it's being moved
+pub fn move_button_label(fee: i32, cooldown: Option<f32>, moving: bool) -> String {
+    if moving {
+        "Moving...".to_string()
+    } else if let Some(remaining) = cooldown {
+        format!("Move ({:.0}s)", remaining.ceil())
+    } else {
+        format!("Move (${})", fee)
+    }
+}
+
+/// Pick the selected tower up so the next click on the arena puts it down
+pub fn handle_move_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<MoveButton>)>,
+    tower_query: Query<(&Tower, Option<&RelocationCooldown>)>,
+    selected: Res<SelectedTower>,
+    game_state: Res<GameState>,
+    config: Res<GameConfig>,
+    mut relocating: ResMut<Relocating>,
+    mut status: ResMut<StatusMessage>,
+) {
+    if !interaction_query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    let Some((entity, (tower, cooldown))) = selected
+        .0
+        .and_then(|entity| Some((entity, tower_query.get(entity).ok()?)))
+    else {
+        return;
+    };
+
+    if let Some(cooldown) = cooldown {
+        status.show(format!(
+            "Can move again in {:.0}s",
+            cooldown.0.remaining_secs().ceil()
+        ));
+        return;
+    }
+
+    if game_state.money < config.relocation_fee {
+        status.show("Not enough money");
+        return;
+    }
+
+    relocating.0 = Some(entity);
+    status.show(format!("Click where to move the {}", tower.kind.name()));
+}
+
+/// Put a picked up tower down where the player clicks. The tower keeps its
+/// upgrades, rank and combat record; only its position changes.
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
+pub fn handle_relocation(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    interaction_query: Query<&Interaction>,
+    mut tower_query: Query<(Entity, &mut Transform), With<Tower>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut game_state: ResMut<GameState>,
+    mut relocating: ResMut<Relocating>,
+    mut status: ResMut<StatusMessage>,
+    config: Res<GameConfig>,
+) {
+    let Some(moving) = relocating.0 else {
+        return;
+    };
+
+    if !mouse_button.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    if interaction_query.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+
+    let Some(world_pos) = cursor_world_position(&windows, &camera_query) else {
+        return;
+    };
+
+    let (position, result) = check_relocation_spot(
+        world_pos,
+        game_state.money,
+        config.relocation_fee,
+        moving,
+        tower_query
+            .iter()
+            .map(|(entity, transform)| (entity, transform.translation.truncate())),
+        &config,
+    );
+
+    if let Err(error) = result {
+        status.show(error.message());
+        spawn_refused_marker(&mut commands, &mut meshes, &mut materials, position);
+        return;
+    }
+
+    let Ok((_, mut transform)) = tower_query.get_mut(moving) else {
+        relocating.0 = None;
+        return;
+    };
+
+    game_state.money -= config.relocation_fee;
+    transform.translation = position.extend(transform.translation.z);
+    commands
+        .entity(moving)
+        .insert(RelocationCooldown(Timer::from_seconds(
+            config.relocation_cooldown,
+            TimerMode::Once,
+        )));
+    relocating.0 = None;
+}
+
+pub fn tick_relocation_cooldowns(
+    mut cooldown_query: Query<(Entity, &mut RelocationCooldown)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut cooldown) in cooldown_query.iter_mut() {
+        cooldown.0.tick(time.delta());
+        if cooldown.0.finished() {
+            commands.entity(entity).remove::<RelocationCooldown>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_button_label() {
+        assert_eq!(move_button_label(15, None, false), "Move ($15)");
+        assert_eq!(move_button_label(15, Some(11.2), false), "Move (12s)");
+        assert_eq!(move_button_label(15, None, true), "Moving...");
+    }
+}
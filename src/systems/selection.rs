@@ -0,0 +1,194 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+/// Money returned for selling a tower. Towers sold during the wave they were
+/// placed in are refunded in full so misclicks aren't punished.
+pub fn sell_value(investment: &TowerInvestment, current_wave: u32, refund_ratio: f32) -> i32 {
+    if investment.placed_wave == current_wave {
+        investment.spent
+    } else {
+        (investment.spent as f32 * refund_ratio).floor() as i32
+    }
+}
+
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
+pub fn handle_sell_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<SellButton>)>,
+    tower_query: Query<(&Tower, &TowerInvestment, &CombatRecord)>,
+    mut commands: Commands,
+    mut selected: ResMut<SelectedTower>,
+    mut game_state: ResMut<GameState>,
+    mut history: ResMut<CombatHistory>,
+    wave_timer: Res<WaveTimer>,
+    config: Res<GameConfig>,
+) {
+    if !interaction_query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    let Some(entity) = selected.0 else {
+        return;
+    };
+
+    if let Ok((tower, investment, record)) = tower_query.get(entity) {
+        game_state.money += sell_value(investment, wave_timer.wave, config.sell_refund_ratio);
+        if tower.kind.attacks() {
+            history.0.push((tower.kind, record.clone()));
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+
+    selected.0 = None;
+}
+
+pub fn handle_upgrade_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<UpgradeButton>)>,
+    mut tower_query: Query<(&mut Tower, &mut TowerInvestment, Option<&mut Aura>)>,
+    selected: Res<SelectedTower>,
+    mut game_state: ResMut<GameState>,
+    mut status: ResMut<StatusMessage>,
+) {
+    if !interaction_query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    let Some((mut tower, mut investment, aura)) = selected
+        .0
+        .and_then(|entity| tower_query.get_mut(entity).ok())
+    else {
+        return;
+    };
+
+    if tower.level >= Tower::MAX_LEVEL {
+        status.show("Already at max level");
+        return;
+    }
+
+    let cost = tower.kind.upgrade_cost(tower.level);
+    if game_state.money < cost {
+        status.show("Not enough money");
+        return;
+    }
+
+    game_state.money -= cost;
+    investment.spent += cost;
+    tower.upgrade();
+
+    if let (Some(mut aura), Some(upgraded)) = (aura, tower.kind.aura(tower.level)) {
+        *aura = upgraded;
+    }
+}
+
+pub fn handle_targeting_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<TargetingButton>)>,
+    selected: Res<SelectedTower>,
+    mut tower_query: Query<&mut Tower>,
+) {
+    if !interaction_query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    if let Some(mut tower) = selected
+        .0
+        .and_then(|entity| tower_query.get_mut(entity).ok())
+    {
+        tower.targeting = tower.targeting.next();
+    }
+}
+
+pub fn handle_lead_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<LeadButton>)>,
+    selected: Res<SelectedTower>,
+    mut tower_query: Query<&mut Tower>,
+) {
+    if !interaction_query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    if let Some(mut tower) = selected
+        .0
+        .and_then(|entity| tower_query.get_mut(entity).ok())
+    {
+        tower.lead_targeting = !tower.lead_targeting;
+    }
+}
+
+pub fn clear_selection(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut selected: ResMut<SelectedTower>,
+    mut relocating: ResMut<Relocating>,
+    tower_query: Query<(), With<Tower>>,
+) {
+    let stale = selected
+        .0
+        .is_some_and(|entity| tower_query.get(entity).is_err());
+
+    if stale || keyboard.just_pressed(KeyCode::Escape) {
+        selected.0 = None;
+    }
+
+    // Escape also puts a tower being moved back where it was
+    let moved_away = relocating
+        .0
+        .is_some_and(|entity| tower_query.get(entity).is_err());
+    if moved_away || keyboard.just_pressed(KeyCode::Escape) {
+        relocating.0 = None;
+    }
+}
+
+/// Outline the selected tower and show its range
+pub fn draw_selection(
+    selected: Res<SelectedTower>,
+    tower_query: Query<(&Transform, &EffectiveStats)>,
+    mut gizmos: Gizmos,
+) {
+    let Some(entity) = selected.0 else {
+        return;
+    };
+
+    if let Ok((transform, stats)) = tower_query.get(entity) {
+        let position = transform.translation.truncate();
+        gizmos.rect_2d(position, Vec2::splat(30.0), Color::WHITE);
+        gizmos.circle_2d(position, stats.range, Color::srgba(1.0, 1.0, 1.0, 0.4));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sell_value_same_wave_is_full_refund() {
+        let investment = TowerInvestment {
+            spent: 20,
+            placed_wave: 2,
+        };
+
+        assert_eq!(sell_value(&investment, 2, 0.7), 20);
+    }
+
+    #[test]
+    fn test_sell_value_later_wave_is_partial_refund() {
+        let investment = TowerInvestment {
+            spent: 20,
+            placed_wave: 1,
+        };
+
+        assert_eq!(sell_value(&investment, 2, 0.7), 14);
+        assert_eq!(sell_value(&investment, 5, 0.5), 10);
+    }
+
+    #[test]
+    fn test_sell_value_never_exceeds_spent() {
+        let investment = TowerInvestment {
+            spent: 35,
+            placed_wave: 1,
+        };
+
+        for ratio in [0.0, 0.25, 0.7, 1.0] {
+            let value = sell_value(&investment, 3, ratio);
+            assert!((0..=35).contains(&value));
+        }
+    }
+}
@@ -6,10 +6,34 @@ pub fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<GameConfig>,
 ) {
     // Camera
     commands.spawn(Camera2d);
 
+    // Draw unbuildable terrain
+    for zone in &config.blocked_zones {
+        commands.spawn((
+            Mesh2d(meshes.add(Rectangle::from_size(zone.size()))),
+            MeshMaterial2d(materials.add(Color::srgb(0.15, 0.3, 0.55))),
+            Transform::from_translation(zone.center().extend(-1.0)),
+        ));
+    }
+
+    // Draw rocks and walls
+    let obstacle_color = Color::srgb(0.45, 0.42, 0.4);
+    for obstacle in &config.obstacles {
+        let (mesh, center) = match obstacle {
+            Obstacle::Rock { center, radius } => (meshes.add(Circle::new(*radius)), *center),
+            Obstacle::Wall(rect) => (meshes.add(Rectangle::from_size(rect.size())), rect.center()),
+        };
+        commands.spawn((
+            Mesh2d(mesh),
+            MeshMaterial2d(materials.add(obstacle_color)),
+            Transform::from_translation(center.extend(-0.5)),
+        ));
+    }
+
     // Draw path
     for i in 0..ENEMY_PATH.len() - 1 {
         let start = Vec3::new(ENEMY_PATH[i].0, ENEMY_PATH[i].1, 0.0);
@@ -30,6 +54,25 @@ pub fn setup(
         }
     }
 
+    // Preview of the tower under the cursor, positioned and shown by the placement system
+    commands.spawn((
+        Mesh2d(meshes.add(Rectangle::new(24.0, 24.0))),
+        MeshMaterial2d(materials.add(Color::srgba(0.3, 1.0, 0.4, 0.5))),
+        Transform::from_xyz(0.0, 0.0, 4.0),
+        Visibility::Hidden,
+        PlacementGhost,
+    ));
+
+    // The hero starts next to the first bend in the path
+    commands.spawn((
+        Mesh2d(meshes.add(RegularPolygon::new(12.0, 6))),
+        MeshMaterial2d(materials.add(Color::srgb(1.0, 0.55, 0.1))),
+        Transform::from_translation(HERO_SPAWN.extend(2.0)),
+        Hero::default(),
+        Reload::default(),
+        CombatRecord::default(),
+    ));
+
     // UI
     commands
         .spawn((
@@ -82,17 +125,38 @@ pub fn setup(
                     ));
                 });
 
+            // Hero display
+            parent
+                .spawn((Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(10.0),
+                    top: Val::Px(70.0),
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Hero level 1"),
+                        TextFont {
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 0.7, 0.4)),
+                        HeroText,
+                    ));
+                });
+
             // Instructions
             parent
                 .spawn((Node {
                     position_type: PositionType::Absolute,
-                    right: Val::Px(10.0),
-                    bottom: Val::Px(10.0),
+                    width: Val::Percent(100.0),
+                    top: Val::Px(10.0),
+                    justify_content: JustifyContent::Center,
                     ..default()
                 },))
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Click to place towers"),
+                        Text::new("Pick a tower or trap below, click to place, click a tower to select it, right-click to move the hero"),
                         TextFont {
                             font_size: 18.0,
                             ..default()
@@ -100,5 +164,175 @@ pub fn setup(
                         TextColor(Color::srgb(0.8, 0.8, 0.8)),
                     ));
                 });
+
+            // Build bar
+            parent
+                .spawn((Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    bottom: Val::Px(8.0),
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    for choice in BuildChoice::all() {
+                        spawn_build_card(parent, choice);
+                    }
+                });
+
+            // Status message, e.g. why a placement was rejected
+            parent
+                .spawn((Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Px(80.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 0.5, 0.4)),
+                        Visibility::Hidden,
+                        StatusText,
+                    ));
+                });
+
+            // Selected tower panel, hidden until a tower is clicked
+            parent
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(10.0),
+                        top: Val::Px(10.0),
+                        width: Val::Px(180.0),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        row_gap: Val::Px(6.0),
+                        flex_direction: FlexDirection::Column,
+                        display: Display::None,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.85)),
+                    TowerPanel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        TowerInfoText,
+                    ));
+
+                    spawn_panel_button(
+                        parent,
+                        "Target: Closest",
+                        Color::srgb(0.25, 0.35, 0.55),
+                        TargetingButton,
+                    );
+                    spawn_panel_button(
+                        parent,
+                        "Lead: On",
+                        Color::srgb(0.25, 0.35, 0.55),
+                        LeadButton,
+                    );
+                    spawn_panel_button(
+                        parent,
+                        "Ability",
+                        Color::srgb(0.55, 0.35, 0.6),
+                        AbilityButton,
+                    );
+                    spawn_panel_button(
+                        parent,
+                        "Move",
+                        Color::srgb(0.45, 0.45, 0.3),
+                        MoveButton,
+                    );
+                    spawn_panel_button(
+                        parent,
+                        "Upgrade",
+                        Color::srgb(0.25, 0.5, 0.3),
+                        UpgradeButton,
+                    );
+                    spawn_panel_button(parent, "Sell", Color::srgb(0.6, 0.25, 0.2), SellButton);
+                });
+        });
+}
+
+fn spawn_panel_button(parent: &mut ChildBuilder, label: &str, color: Color, marker: impl Bundle) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(color),
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+fn spawn_build_card(parent: &mut ChildBuilder, choice: BuildChoice) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(76.0),
+                padding: UiRect::all(Val::Px(4.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.15, 0.15, 0.2, 0.9)),
+            BorderColor(Color::srgb(0.3, 0.3, 0.35)),
+            BuildCard(choice),
+        ))
+        .with_children(|parent| {
+            // Icon
+            parent.spawn((
+                Node {
+                    width: Val::Px(18.0),
+                    height: Val::Px(18.0),
+                    ..default()
+                },
+                BackgroundColor(choice.color()),
+            ));
+
+            parent.spawn((
+                Text::new(choice.name()),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            parent.spawn((
+                Text::new(format!("${}  [{}]", choice.cost(), choice.hotkey_label())),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
         });
 }
@@ -0,0 +1,162 @@
+use super::synergy::active_synergies;
+use crate::components::*;
+use bevy::prelude::*;
+
+/// Changes to a tower that can alter its own or its neighbours' effective stats
+type StatsChanged = Or<(Changed<Tower>, Changed<Rank>, Changed<Ability>)>;
+
+/// Everything that feeds into a tower's effective stats
+type StatSources<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Tower,
+    &'a Rank,
+    Option<&'a Ability>,
+    &'a mut EffectiveStats,
+);
+
+/// Modifiers that auras grant to an attacking tower at `position`. Towers
+/// never buff themselves.
+pub fn aura_modifiers(
+    tower: Entity,
+    position: Vec2,
+    auras: &[(Entity, Vec2, Aura)],
+) -> Vec<StatModifier> {
+    auras
+        .iter()
+        .filter(|(source, source_position, aura)| {
+            *source != tower && position.distance(*source_position) <= aura.radius
+        })
+        .map(|(_, _, aura)| aura.modifier)
+        .collect()
+}
+
+/// Promote towers whose combat record has earned a higher rank, adding a
+/// pip above the tower for each rank gained
+pub fn promote_veterans(
+    mut tower_query: Query<(Entity, &CombatRecord, &mut Rank), Changed<CombatRecord>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, record, mut rank) in tower_query.iter_mut() {
+        let earned = Rank::for_record(record);
+        if earned.0 <= rank.0 {
+            continue;
+        }
+
+        for pip in rank.0..earned.0 {
+            let x = -9.0 + pip as f32 * 6.0;
+            let insignia = commands
+                .spawn((
+                    Mesh2d(meshes.add(Rectangle::new(4.0, 4.0))),
+                    MeshMaterial2d(materials.add(Color::srgb(1.0, 0.85, 0.2))),
+                    Transform::from_xyz(x, 17.0, 0.5),
+                    RankInsignia,
+                ))
+                .id();
+            commands.entity(entity).add_child(insignia);
+        }
+
+        *rank = earned;
+    }
+}
+
+/// Rebuild every tower's effective stats when a tower is placed, changed,
+/// promoted, moved, uses or runs out an ability, or is removed, since any of those
+/// can move auras or synergy partners in or out of range or change a tower's
+/// bonuses
+pub fn recompute_tower_stats(
+    // Newly added towers count as changed too
+    changed_query: Query<(), StatsChanged>,
+    moved_query: Query<(), (With<Tower>, Changed<Transform>)>,
+    mut removed: RemovedComponents<Tower>,
+    aura_query: Query<(Entity, &Transform, &Aura), With<Tower>>,
+    placed_query: Query<(Entity, &Tower, &Transform)>,
+    mut tower_query: Query<StatSources>,
+) {
+    let any_removed = removed.read().count() > 0;
+    if changed_query.is_empty() && moved_query.is_empty() && !any_removed {
+        return;
+    }
+
+    let auras: Vec<_> = aura_query
+        .iter()
+        .map(|(entity, transform, aura)| (entity, transform.translation.truncate(), *aura))
+        .collect();
+    let towers: Vec<_> = placed_query
+        .iter()
+        .map(|(entity, tower, transform)| (entity, tower.kind, transform.translation.truncate()))
+        .collect();
+
+    for (entity, transform, tower, rank, ability, mut stats) in tower_query.iter_mut() {
+        let modifiers = if tower.kind.attacks() {
+            let position = transform.translation.truncate();
+            let mut modifiers = aura_modifiers(entity, position, &auras);
+            modifiers.push(rank.modifier());
+            modifiers.extend(
+                active_synergies(entity, tower.kind, position, &towers)
+                    .iter()
+                    .map(|synergy| synergy.modifier),
+            );
+            if let Some(ability) = ability.filter(|ability| ability.is_active()) {
+                modifiers.push(ability.kind.modifier());
+            }
+            modifiers
+        } else {
+            Vec::new()
+        };
+
+        let new_stats = EffectiveStats::compute(tower, &modifiers);
+        if *stats != new_stats {
+            *stats = new_stats;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn support_aura() -> Aura {
+        TowerKind::Support.aura(1).unwrap()
+    }
+
+    #[test]
+    fn test_aura_in_range_applies() {
+        let auras = [(Entity::from_raw(1), Vec2::ZERO, support_aura())];
+        let modifiers = aura_modifiers(Entity::from_raw(2), Vec2::new(50.0, 0.0), &auras);
+
+        assert_eq!(modifiers, vec![support_aura().modifier]);
+    }
+
+    #[test]
+    fn test_aura_out_of_range_ignored() {
+        let auras = [(Entity::from_raw(1), Vec2::ZERO, support_aura())];
+        let modifiers = aura_modifiers(Entity::from_raw(2), Vec2::new(500.0, 0.0), &auras);
+
+        assert!(modifiers.is_empty());
+    }
+
+    #[test]
+    fn test_aura_does_not_buff_itself() {
+        let auras = [(Entity::from_raw(1), Vec2::ZERO, support_aura())];
+        let modifiers = aura_modifiers(Entity::from_raw(1), Vec2::ZERO, &auras);
+
+        assert!(modifiers.is_empty());
+    }
+
+    #[test]
+    fn test_auras_stack() {
+        let auras = [
+            (Entity::from_raw(1), Vec2::new(-40.0, 0.0), support_aura()),
+            (Entity::from_raw(2), Vec2::new(40.0, 0.0), support_aura()),
+        ];
+        let modifiers = aura_modifiers(Entity::from_raw(3), Vec2::ZERO, &auras);
+        let stats = EffectiveStats::compute(&TowerKind::Gun.tower(), &modifiers);
+
+        let base = TowerKind::Gun.tower();
+        let bonus = support_aura().modifier.damage * 2.0;
+        assert!((stats.damage - base.damage * (1.0 + bonus)).abs() < 0.001);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+/// Synergies a tower of `kind` at `position` has with the towers around it.
+/// `towers` is every tower on the map, which may include this one.
+pub fn active_synergies(
+    tower: Entity,
+    kind: TowerKind,
+    position: Vec2,
+    towers: &[(Entity, TowerKind, Vec2)],
+) -> Vec<&'static Synergy> {
+    SYNERGIES
+        .iter()
+        .filter(|synergy| synergy.beneficiary == kind)
+        .filter(|synergy| {
+            towers.iter().any(|(other, other_kind, other_position)| {
+                *other != tower
+                    && *other_kind == synergy.partner
+                    && position.distance(*other_position) <= synergy.distance
+            })
+        })
+        .collect()
+}
+
+/// Every pair of towers currently in a synergy, with the colour to link them in
+pub fn synergy_links(towers: &[(Entity, TowerKind, Vec2)]) -> Vec<(Vec2, Vec2, Color)> {
+    let mut links = Vec::new();
+
+    for synergy in &SYNERGIES {
+        for (tower, kind, position) in towers {
+            if *kind != synergy.beneficiary {
+                continue;
+            }
+
+            for (other, other_kind, other_position) in towers {
+                if other != tower
+                    && *other_kind == synergy.partner
+                    && position.distance(*other_position) <= synergy.distance
+                {
+                    links.push((*position, *other_position, synergy.color));
+                }
+            }
+        }
+    }
+
+    links
+}
+
+/// Draw a line between every pair of synergised towers
+pub fn draw_synergy_links(tower_query: Query<(Entity, &Tower, &Transform)>, mut gizmos: Gizmos) {
+    let towers: Vec<_> = tower_query
+        .iter()
+        .map(|(entity, tower, transform)| (entity, tower.kind, transform.translation.truncate()))
+        .collect();
+
+    for (from, to, color) in synergy_links(&towers) {
+        gizmos.line_2d(from, to, color.with_alpha(0.6));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tower(id: u32, kind: TowerKind, x: f32) -> (Entity, TowerKind, Vec2) {
+        (Entity::from_raw(id), kind, Vec2::new(x, 0.0))
+    }
+
+    #[test]
+    fn test_synergy_needs_partner_in_range() {
+        let towers = [
+            tower(1, TowerKind::Sniper, 0.0),
+            tower(2, TowerKind::Support, 80.0),
+        ];
+        let synergies = active_synergies(towers[0].0, TowerKind::Sniper, Vec2::ZERO, &towers);
+
+        assert_eq!(synergies.len(), 1);
+        assert_eq!(synergies[0].name, "Shatter");
+        assert!(synergies[0].modifier.slowed_damage > 0.0);
+
+        let far = [
+            tower(1, TowerKind::Sniper, 0.0),
+            tower(2, TowerKind::Support, 400.0),
+        ];
+        assert!(active_synergies(far[0].0, TowerKind::Sniper, Vec2::ZERO, &far).is_empty());
+    }
+
+    #[test]
+    fn test_synergy_applies_once_for_several_partners() {
+        let towers = [
+            tower(1, TowerKind::Sniper, 0.0),
+            tower(2, TowerKind::Support, 60.0),
+            tower(3, TowerKind::Support, -60.0),
+        ];
+        let synergies = active_synergies(towers[0].0, TowerKind::Sniper, Vec2::ZERO, &towers);
+
+        assert_eq!(synergies.len(), 1);
+    }
+
+    #[test]
+    fn test_synergy_only_benefits_its_beneficiary() {
+        let towers = [
+            tower(1, TowerKind::Sniper, 0.0),
+            tower(2, TowerKind::Support, 80.0),
+        ];
+
+        // The Support tower enables Shatter but doesn't get it
+        assert!(active_synergies(
+            towers[1].0,
+            TowerKind::Support,
+            Vec2::new(80.0, 0.0),
+            &towers
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_synergy_links_one_per_pair() {
+        let towers = [
+            tower(1, TowerKind::Sniper, 0.0),
+            tower(2, TowerKind::Support, 60.0),
+            tower(3, TowerKind::Support, -60.0),
+            tower(4, TowerKind::Farm, 30.0),
+        ];
+        let links = synergy_links(&towers);
+
+        assert_eq!(links.len(), 2);
+        assert!(links.iter().all(|(from, _, _)| *from == Vec2::ZERO));
+    }
+}
@@ -0,0 +1,218 @@
+use super::enemy::calculate_position_on_path;
+use crate::components::*;
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+/// Refinement passes for `predict_intercept`. Each pass re-estimates the
+/// flight time from the last guess; with enemies much slower than bullets this
+/// converges to well under a pixel.
+const INTERCEPT_ITERATIONS: usize = 8;
+
+/// The parts of an enemy that target selection cares about
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TargetCandidate {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub path_progress: f32,
+    pub health: f32,
+    pub speed: f32,
+}
+
+/// Pick the candidate within `range` of `origin` that best matches `mode`
+pub fn select_target(
+    origin: Vec2,
+    range: f32,
+    mode: TargetingMode,
+    candidates: impl IntoIterator<Item = TargetCandidate>,
+) -> Option<TargetCandidate> {
+    candidates
+        .into_iter()
+        .filter(|candidate| origin.distance(candidate.position) <= range)
+        .map(|candidate| {
+            let score = match mode {
+                TargetingMode::First => candidate.path_progress,
+                TargetingMode::Last => -candidate.path_progress,
+                TargetingMode::Strongest => candidate.health,
+                TargetingMode::Weakest => -candidate.health,
+                TargetingMode::Closest => -origin.distance(candidate.position),
+                TargetingMode::Fastest => candidate.speed,
+            };
+            (candidate, score)
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(candidate, _)| candidate)
+}
+
+/// Where to aim a projectile fired now from `origin` so it meets an enemy that
+/// keeps following the path at its current speed, including around corners
+pub fn predict_intercept(
+    origin: Vec2,
+    projectile_speed: f32,
+    path_progress: f32,
+    enemy_speed: f32,
+) -> Vec2 {
+    let mut aim = calculate_position_on_path(path_progress).truncate();
+
+    for _ in 0..INTERCEPT_ITERATIONS {
+        let flight_time = origin.distance(aim) / projectile_speed;
+        aim = calculate_position_on_path(path_progress + enemy_speed * flight_time).truncate();
+    }
+
+    aim
+}
+
+/// Signed shortest rotation from angle `from` to angle `to`, in (-PI, PI]
+pub fn angle_difference(from: f32, to: f32) -> f32 {
+    let difference = (to - from).rem_euclid(TAU);
+    if difference > PI {
+        difference - TAU
+    } else {
+        difference
+    }
+}
+
+/// Turn from `current` toward `target` by at most `max_step` radians
+pub fn rotate_towards(current: f32, target: f32, max_step: f32) -> f32 {
+    let difference = angle_difference(current, target);
+    let step = difference.clamp(-max_step, max_step);
+    (current + step).rem_euclid(TAU)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn candidate(
+        index: u32,
+        x: f32,
+        path_progress: f32,
+        health: f32,
+        speed: f32,
+    ) -> TargetCandidate {
+        TargetCandidate {
+            entity: Entity::from_raw(index),
+            position: Vec2::new(x, 0.0),
+            path_progress,
+            health,
+            speed,
+        }
+    }
+
+    /// Three enemies in range where each mode picks a different winner than
+    /// its opposite, plus one far along, strong and fast enemy out of range
+    fn candidates() -> Vec<TargetCandidate> {
+        vec![
+            candidate(0, 60.0, 300.0, 40.0, 50.0),
+            candidate(1, 90.0, 100.0, 100.0, 30.0),
+            candidate(2, 30.0, 200.0, 70.0, 80.0),
+            candidate(3, 500.0, 900.0, 500.0, 200.0),
+        ]
+    }
+
+    fn selected(mode: TargetingMode) -> Option<u32> {
+        select_target(Vec2::ZERO, 100.0, mode, candidates()).map(|c| c.entity.index())
+    }
+
+    #[test]
+    fn test_select_first() {
+        assert_eq!(selected(TargetingMode::First), Some(0));
+    }
+
+    #[test]
+    fn test_select_last() {
+        assert_eq!(selected(TargetingMode::Last), Some(1));
+    }
+
+    #[test]
+    fn test_select_strongest() {
+        assert_eq!(selected(TargetingMode::Strongest), Some(1));
+    }
+
+    #[test]
+    fn test_select_weakest() {
+        assert_eq!(selected(TargetingMode::Weakest), Some(0));
+    }
+
+    #[test]
+    fn test_select_closest() {
+        assert_eq!(selected(TargetingMode::Closest), Some(2));
+    }
+
+    #[test]
+    fn test_select_fastest() {
+        assert_eq!(selected(TargetingMode::Fastest), Some(2));
+    }
+
+    #[test]
+    fn test_select_nothing_in_range() {
+        for mode in TargetingMode::ALL {
+            assert_eq!(select_target(Vec2::ZERO, 10.0, mode, candidates()), None);
+        }
+    }
+
+    #[test]
+    fn test_predict_intercept_stationary_enemy() {
+        let aim = predict_intercept(Vec2::new(-300.0, 100.0), 300.0, 100.0, 0.0);
+
+        // A stationary enemy halfway along the first segment
+        assert_eq!(aim, Vec2::new(-300.0, 200.0));
+    }
+
+    #[test]
+    fn test_predict_intercept_leads_moving_enemy() {
+        let origin = Vec2::new(-300.0, 100.0);
+        let (progress, speed, bullet_speed) = (100.0, 50.0, 300.0);
+        let aim = predict_intercept(origin, bullet_speed, progress, speed);
+
+        // Aim ahead of the enemy along the first (rightward) segment
+        assert_eq!(aim.y, 200.0);
+        assert!(aim.x > -300.0);
+
+        // Bullet and enemy arrive at the aim point at the same time
+        let bullet_time = origin.distance(aim) / bullet_speed;
+        let enemy_time = (aim.x - -300.0) / speed;
+        assert!((bullet_time - enemy_time).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_predict_intercept_around_corner() {
+        // Just before the first corner at (-200, 200), where the path turns downward
+        let origin = Vec2::new(-100.0, 100.0);
+        let (progress, speed, bullet_speed) = (195.0, 50.0, 300.0);
+        let aim = predict_intercept(origin, bullet_speed, progress, speed);
+
+        // The aim point is past the corner, on the downward segment
+        assert_eq!(aim.x, -200.0);
+        assert!(aim.y < 200.0);
+
+        let bullet_time = origin.distance(aim) / bullet_speed;
+        let enemy_distance = 5.0 + (200.0 - aim.y);
+        assert!((bullet_time - enemy_distance / speed).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_angle_difference_takes_short_way_round() {
+        assert!((angle_difference(0.0, FRAC_PI_2) - FRAC_PI_2).abs() < 1e-5);
+        assert!((angle_difference(FRAC_PI_2, 0.0) + FRAC_PI_2).abs() < 1e-5);
+        // Across the wrap-around from just below TAU to just above zero
+        assert!((angle_difference(TAU - 0.1, 0.1) - 0.2).abs() < 1e-5);
+        assert!((angle_difference(0.1, TAU - 0.1) + 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_rotate_towards_limited_by_step() {
+        let turned = rotate_towards(0.0, PI / 2.0, 0.5);
+        assert!((turned - 0.5).abs() < 1e-5);
+
+        // Clockwise is shorter here, so it wraps below zero
+        let turned = rotate_towards(0.2, TAU - 0.2, 0.1);
+        assert!((turned - 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_rotate_towards_reaches_target() {
+        let turned = rotate_towards(1.0, 1.2, 0.5);
+        assert!((turned - 1.2).abs() < 1e-5);
+    }
+}
@@ -1,42 +1,131 @@
+use super::combat::roll_damage;
+use super::placement::{check_build_spot, check_trap_spot, cursor_world_position};
 use super::sound::{create_sound_effect_visual, play_console_beep, SoundType};
+use super::targeting::{
+    angle_difference, predict_intercept, rotate_towards, select_target, TargetCandidate,
+};
+use super::traps::spawn_trap;
 use crate::components::*;
 use crate::resources::*;
 use bevy::prelude::*;
 
+const BULLET_SPEED: f32 = 300.0;
+
+/// Everything a tower needs to aim and fire, including its turret child
+type Shooter<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Tower,
+    &'a EffectiveStats,
+    &'a mut Reload,
+    &'a mut CombatRecord,
+    Option<&'a Children>,
+    Option<&'a mut Beam>,
+);
+
+/// What towers need to know about an enemy to pick and lead a target
+type EnemyTarget<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Enemy,
+    Has<Stealth>,
+    Option<&'a Slowed>,
+);
+
+/// Turrets are children of towers, kept disjoint from tower and enemy transforms
+type TurretOnly = (Without<Tower>, Without<Enemy>);
+
+#[allow(clippy::too_many_arguments)] // System functions often need many parameters
 pub fn tower_shooting(
-    mut tower_query: Query<(&Transform, &mut Tower)>,
-    enemy_query: Query<&Transform, (With<Enemy>, Without<Tower>)>,
+    mut tower_query: Query<Shooter>,
+    mut turret_query: Query<(&mut Turret, &mut Transform), TurretOnly>,
+    enemy_query: Query<EnemyTarget, Without<Tower>>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut strikes: EventWriter<LightningStrike>,
+    mut rng: ResMut<GameRng>,
+    config: Res<GameConfig>,
     time: Res<Time>,
 ) {
-    for (tower_transform, mut tower) in tower_query.iter_mut() {
-        tower.last_shot += time.delta_secs();
+    for (tower_entity, tower_transform, tower, stats, mut reload, mut record, children, mut beam) in
+        tower_query.iter_mut()
+    {
+        let Some(weapon) = tower.kind.weapon() else {
+            continue;
+        };
+
+        // Time passing isn't an achievement, so don't wake up the systems
+        // that watch the record for kills and damage
+        record.bypass_change_detection().time_active += time.delta_secs();
+        let interval = 1.0 / stats.fire_rate;
+
+        let origin = tower_transform.translation.truncate();
 
-        if tower.last_shot < 1.0 / tower.fire_rate {
+        // Pick a target every frame so turrets can track it while reloading.
+        // Enemies hidden behind scenery can't be seen.
+        let candidates = enemy_query
+            .iter()
+            .filter(|(_, _, enemy, stealth, _)| {
+                enemy.health > 0.0 && (!stealth || stats.reveals_stealth)
+            })
+            .filter(|(_, transform, _, _, _)| {
+                !config.sight_blocked(origin, transform.translation.truncate())
+            })
+            .map(|(entity, transform, enemy, _, slowed)| TargetCandidate {
+                entity,
+                position: transform.translation.truncate(),
+                path_progress: enemy.path_progress,
+                health: enemy.health,
+                speed: enemy.current_speed(slowed),
+            });
+        let target = select_target(origin, stats.range, tower.targeting, candidates);
+
+        let Some(target) = target else {
+            reload.tick(time.delta_secs(), interval, false);
+            if let Some(beam) = beam.as_mut() {
+                beam.lock(None, time.delta_secs());
+            }
             continue;
-        }
+        };
+
+        // Leading a shot can send it into scenery even though the target is in
+        // sight, so aim straight at the target instead. Beams and lightning
+        // hit instantly and never lead.
+        let lead = (tower.lead_targeting && matches!(weapon, Weapon::Projectile(_)))
+            .then(|| predict_intercept(origin, BULLET_SPEED, target.path_progress, target.speed))
+            .filter(|lead| !config.sight_blocked(origin, *lead));
+        let aim = lead.unwrap_or(target.position);
+        let direction = (aim - origin).normalize_or_zero();
 
-        // Find closest enemy in range
-        let mut closest_enemy: Option<Vec3> = None;
-        let mut closest_distance = f32::MAX;
-
-        for enemy_transform in enemy_query.iter() {
-            let distance = tower_transform
-                .translation
-                .distance(enemy_transform.translation);
-            if distance <= tower.range && distance < closest_distance {
-                closest_distance = distance;
-                closest_enemy = Some(enemy_transform.translation);
+        // Turn the turret toward the aim point; it can only fire once lined up
+        let mut aligned = true;
+        let turret = children
+            .into_iter()
+            .flatten()
+            .find(|child| turret_query.contains(**child));
+        if let Some(&turret_entity) = turret {
+            if let Ok((mut turret, mut turret_transform)) = turret_query.get_mut(turret_entity) {
+                let desired = direction.to_angle();
+                turret.facing =
+                    rotate_towards(turret.facing, desired, turret.turn_rate * time.delta_secs());
+                turret_transform.rotation = Quat::from_rotation_z(turret.facing);
+                aligned = angle_difference(turret.facing, desired).abs() <= turret.tolerance;
             }
         }
 
-        if let Some(enemy_pos) = closest_enemy {
-            tower.last_shot = 0.0;
-
-            // Create bullet
-            let direction = (enemy_pos - tower_transform.translation).normalize_or_zero();
+        // Beams stay on the target while the turret is lined up and are
+        // burnt in by beam_damage; starting a lock counts as a shot that hits
+        let shots = match (weapon, beam.as_mut()) {
+            (Weapon::Beam, Some(beam)) => {
+                let locked = beam.lock(aligned.then_some(target.entity), time.delta_secs());
+                record.hits += locked as u32;
+                locked as u32
+            }
+            _ => reload.tick(time.delta_secs(), interval, aligned),
+        };
+        if shots > 0 {
+            record.shots_fired += shots;
 
             // Play shooting sound (console beep + visual effect)
             play_console_beep(SoundType::Shoot);
@@ -47,65 +136,293 @@ pub fn tower_shooting(
                 tower_transform.translation,
                 SoundType::Shoot,
             );
+        }
 
+        let kind = match weapon {
+            Weapon::Projectile(kind) => kind,
+            Weapon::Chain { jumps, range } => {
+                for _ in 0..shots {
+                    strikes.send(LightningStrike {
+                        source: tower_entity,
+                        origin,
+                        target: target.entity,
+                        stats: *stats,
+                        jumps,
+                        range,
+                    });
+                }
+                continue;
+            }
+            // Locked on above; beam_damage does the rest
+            Weapon::Beam => continue,
+        };
+
+        // Shots that came due together leave in one burst, each with its own
+        // damage roll
+        for _ in 0..shots {
+            let (damage, critical) = roll_damage(stats, &mut rng);
+            let (mesh, color) = match kind {
+                ProjectileKind::Straight => {
+                    (meshes.add(Circle::new(4.0)), Color::srgb(1.0, 1.0, 0.3))
+                }
+                ProjectileKind::Homing { .. } => (
+                    meshes.add(Rectangle::new(10.0, 4.0)),
+                    Color::srgb(1.0, 0.55, 0.3),
+                ),
+                ProjectileKind::Shell { .. } => {
+                    (meshes.add(Circle::new(6.0)), Color::srgb(0.25, 0.25, 0.25))
+                }
+            };
             commands.spawn((
-                Mesh2d(meshes.add(Circle::new(4.0))),
-                MeshMaterial2d(materials.add(Color::srgb(1.0, 1.0, 0.3))),
-                Transform::from_translation(tower_transform.translation + Vec3::Z),
+                Mesh2d(mesh),
+                MeshMaterial2d(materials.add(color)),
+                Transform::from_translation(tower_transform.translation + Vec3::Z)
+                    .with_rotation(Quat::from_rotation_z(direction.to_angle())),
                 Bullet {
-                    damage: tower.damage,
-                    speed: 300.0,
-                    direction: direction.truncate(),
+                    slowed_damage: stats.slowed_damage,
+                    kind,
+                    target: Some(target.entity),
+                    pierce: tower.kind.pierce(),
+                    critical,
+                    lifetime: match kind {
+                        ProjectileKind::Homing { flight_time, .. } => {
+                            Some(Timer::from_seconds(flight_time, TimerMode::Once))
+                        }
+                        _ => None,
+                    },
+                    ..Bullet::new(damage, BULLET_SPEED, direction, tower_entity)
                 },
             ));
         }
     }
 }
 
+pub fn handle_build_hotkeys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut build_selection: ResMut<BuildSelection>,
+) {
+    const DIGITS: [KeyCode; 9] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+
+    for kind in TowerKind::ALL {
+        if keyboard.just_pressed(DIGITS[kind.hotkey() - 1]) {
+            build_selection.0 = Some(BuildChoice::Tower(kind));
+        }
+    }
+
+    for kind in TrapKind::ALL {
+        if keyboard.just_pressed(kind.hotkey()) {
+            build_selection.0 = Some(BuildChoice::Trap(kind));
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        build_selection.0 = None;
+    }
+}
+
+pub fn handle_build_cards(
+    interaction_query: Query<(&Interaction, &BuildCard), Changed<Interaction>>,
+    mut build_selection: ResMut<BuildSelection>,
+) {
+    for (interaction, card) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            build_selection.0 = Some(card.0);
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)] // System functions often need many parameters
 pub fn handle_input(
     mouse_button: Res<ButtonInput<MouseButton>>,
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
+    interaction_query: Query<&Interaction>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut game_state: ResMut<GameState>,
-    tower_query: Query<&Transform, With<Tower>>,
+    mut selected: ResMut<SelectedTower>,
+    mut status: ResMut<StatusMessage>,
+    build_selection: Res<BuildSelection>,
+    relocating: Res<Relocating>,
+    wave_timer: Res<WaveTimer>,
+    config: Res<GameConfig>,
+    tower_query: Query<(Entity, &Transform), With<Tower>>,
+    trap_query: Query<&Transform, With<Trap>>,
 ) {
-    if mouse_button.just_pressed(MouseButton::Left) && game_state.money >= 20 {
-        let window = windows.single();
-        let (camera, camera_transform) = camera_query.single();
-
-        if let Some(cursor_pos) = window.cursor_position() {
-            let world_pos = camera
-                .viewport_to_world_2d(camera_transform, cursor_pos)
-                .unwrap();
-
-            // Check if position is valid (not too close to other towers)
-            let mut can_place = true;
-            for tower_transform in tower_query.iter() {
-                if world_pos.distance(tower_transform.translation.truncate()) < 60.0 {
-                    can_place = false;
-                    break;
-                }
-            }
+    // While a tower is being moved, clicks put it down instead
+    if !mouse_button.just_pressed(MouseButton::Left) || relocating.0.is_some() {
+        return;
+    }
 
-            if can_place {
-                game_state.money -= 20;
-
-                commands.spawn((
-                    Mesh2d(meshes.add(Rectangle::new(24.0, 24.0))),
-                    MeshMaterial2d(materials.add(Color::srgb(0.3, 0.7, 1.0))),
-                    Transform::from_translation(world_pos.extend(1.0)),
-                    Tower {
-                        damage: 25.0,
-                        range: 100.0,
-                        fire_rate: 1.0,
-                        last_shot: 0.0,
-                    },
-                ));
-            }
+    // Clicks on UI buttons shouldn't fall through to the arena
+    if interaction_query.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+
+    let Some(world_pos) = cursor_world_position(&windows, &camera_query) else {
+        return;
+    };
+
+    // Clicking an existing tower selects it instead of placing a new one
+    if let Some((entity, _)) = tower_query
+        .iter()
+        .find(|(_, transform)| world_pos.distance(transform.translation.truncate()) < 16.0)
+    {
+        selected.0 = Some(entity);
+        return;
+    }
+
+    selected.0 = None;
+
+    let Some(choice) = build_selection.0 else {
+        return;
+    };
+
+    // Commit the placement previewed by the ghost
+    let (position, placement) = match choice {
+        BuildChoice::Tower(kind) => {
+            let (position, result) = check_build_spot(
+                world_pos,
+                game_state.money,
+                kind.cost(),
+                tower_query
+                    .iter()
+                    .map(|(_, transform)| transform.translation.truncate()),
+                &config,
+            );
+            (position, result.map(|_| 0.0))
+        }
+        BuildChoice::Trap(kind) => check_trap_spot(
+            world_pos,
+            game_state.money,
+            kind.cost(),
+            trap_query
+                .iter()
+                .map(|transform| transform.translation.truncate()),
+            &config,
+        ),
+    };
+
+    let path_progress = match placement {
+        Ok(path_progress) => path_progress,
+        Err(error) => {
+            status.show(error.message());
+            spawn_refused_marker(&mut commands, &mut meshes, &mut materials, position);
+            return;
+        }
+    };
+
+    game_state.money -= choice.cost();
+    match choice {
+        BuildChoice::Tower(kind) => {
+            spawn_tower(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                kind,
+                position,
+                wave_timer.wave,
+            );
+        }
+        BuildChoice::Trap(kind) => {
+            spawn_trap(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                kind,
+                position,
+                path_progress,
+            );
         }
     }
 }
+
+/// Flash a red marker where a build or move was refused
+pub fn spawn_refused_marker(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    position: Vec2,
+) {
+    commands.spawn((
+        Mesh2d(meshes.add(Circle::new(10.0))),
+        MeshMaterial2d(materials.add(Color::srgba(1.0, 0.1, 0.1, 0.7))),
+        Transform::from_translation(position.extend(3.0)),
+        HitEffect {
+            timer: Timer::from_seconds(0.3, TimerMode::Once),
+        },
+    ));
+}
+
+pub fn spawn_tower(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    kind: TowerKind,
+    position: Vec2,
+    wave: u32,
+) -> Entity {
+    let tower = kind.tower();
+    let stats = EffectiveStats::compute(&tower, []);
+
+    let mut entity = commands.spawn((
+        Mesh2d(meshes.add(Rectangle::new(24.0, 24.0))),
+        MeshMaterial2d(materials.add(kind.color())),
+        Transform::from_translation(position.extend(1.0)),
+        tower,
+        stats,
+        Reload::default(),
+        CombatRecord::default(),
+        Rank::default(),
+        TowerInvestment {
+            spent: kind.cost(),
+            placed_wave: wave,
+        },
+    ));
+
+    if let Some(aura) = kind.aura(1) {
+        entity.insert(aura);
+    }
+
+    if let Some(ability) = kind.ability() {
+        entity.insert(Ability::new(ability));
+    }
+
+    if kind.weapon() == Some(Weapon::Beam) {
+        entity.insert(Beam::default());
+    }
+
+    // A darker turret head with a barrel pointing along its facing
+    if let Some(turret) = kind.turret() {
+        let turret_color = kind.color().darker(0.2);
+        entity.with_children(|parent| {
+            parent
+                .spawn((
+                    Mesh2d(meshes.add(Circle::new(7.0))),
+                    MeshMaterial2d(materials.add(turret_color)),
+                    Transform::from_xyz(0.0, 0.0, 0.2),
+                    turret,
+                ))
+                .with_children(|turret| {
+                    turret.spawn((
+                        Mesh2d(meshes.add(Rectangle::new(14.0, 5.0))),
+                        MeshMaterial2d(materials.add(turret_color)),
+                        Transform::from_xyz(9.0, 0.0, 0.1),
+                    ));
+                });
+        });
+    }
+
+    entity.id()
+}
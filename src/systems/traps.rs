@@ -0,0 +1,233 @@
+use super::combat::{damage_enemy, spawn_explosion};
+use crate::components::*;
+use bevy::prelude::*;
+
+/// Whether an enemy that moved from `previous` to `current` along the path
+/// stepped over a trap at `trap`
+pub fn crossed(previous: f32, current: f32, trap: f32) -> bool {
+    previous < trap && trap <= current
+}
+
+pub fn spawn_trap(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    kind: TrapKind,
+    position: Vec2,
+    path_progress: f32,
+) -> Entity {
+    let mesh = match kind {
+        TrapKind::Spikes => meshes.add(RegularPolygon::new(9.0, 3)),
+        TrapKind::Mine => meshes.add(Circle::new(7.0)),
+        TrapKind::Glue => meshes.add(Circle::new(13.0)),
+    };
+
+    commands
+        .spawn((
+            Mesh2d(mesh),
+            MeshMaterial2d(materials.add(kind.color())),
+            Transform::from_translation(position.extend(0.5)),
+            Trap {
+                kind,
+                uses_left: kind.uses(),
+                path_progress,
+            },
+        ))
+        .id()
+}
+
+/// Set off traps that enemies walked over this frame. Runs after enemies move,
+/// comparing where each one is with where it was before its step.
+pub fn trigger_traps(
+    mut trap_query: Query<(Entity, &mut Trap, &Transform)>,
+    mut enemy_query: Query<(Entity, &mut Enemy, &Transform), Without<Trap>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (trap_entity, mut trap, trap_transform) in trap_query.iter_mut() {
+        let crossing: Vec<Entity> = enemy_query
+            .iter()
+            .filter(|(_, enemy, _)| {
+                enemy.health > 0.0
+                    && crossed(enemy.last_progress, enemy.path_progress, trap.path_progress)
+            })
+            .map(|(entity, _, _)| entity)
+            .collect();
+
+        if crossing.is_empty() {
+            continue;
+        }
+
+        let trap_position = trap_transform.translation.truncate();
+        let radius = trap.kind.radius();
+        let victims: Vec<Entity> = if radius > 0.0 {
+            // Mines go off once and catch everything around them
+            trap.uses_left = 0;
+            spawn_explosion(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                trap_position,
+                radius,
+            );
+
+            enemy_query
+                .iter()
+                .filter(|(_, enemy, transform)| {
+                    enemy.health > 0.0
+                        && transform.translation.truncate().distance(trap_position) <= radius
+                })
+                .map(|(entity, _, _)| entity)
+                .collect()
+        } else {
+            let count = crossing.len().min(trap.uses_left as usize);
+            trap.uses_left -= count as u32;
+            crossing.into_iter().take(count).collect()
+        };
+
+        for victim in victims {
+            let Ok((entity, mut enemy, _)) = enemy_query.get_mut(victim) else {
+                continue;
+            };
+
+            if let Some((factor, seconds)) = trap.kind.slow() {
+                commands.entity(entity).insert(Slowed {
+                    factor,
+                    timer: Timer::from_seconds(seconds, TimerMode::Once),
+                });
+            }
+
+            if trap.kind.damage() > 0.0 {
+                damage_enemy(&mut commands, entity, &mut enemy, trap.kind.damage(), None);
+            }
+        }
+
+        if trap.uses_left == 0 {
+            commands.entity(trap_entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::enemy::move_enemies;
+    use super::*;
+    use crate::resources::GameState;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_crossed_trap() {
+        assert!(crossed(95.0, 105.0, 100.0));
+        // Landing exactly on the trap counts, starting on it doesn't, so an
+        // enemy can't set the same trap off twice
+        assert!(crossed(95.0, 100.0, 100.0));
+        assert!(!crossed(100.0, 105.0, 100.0));
+    }
+
+    #[test]
+    fn test_not_crossed_trap() {
+        assert!(!crossed(80.0, 90.0, 100.0));
+        assert!(!crossed(110.0, 120.0, 100.0));
+    }
+
+    /// The slow wearing off between an enemy's step and the trap check, on
+    /// the step after the enemy landed on the trap
+    fn slow_wears_off(enemy_query: Query<(Entity, &Enemy), With<Slowed>>, mut commands: Commands) {
+        for (entity, enemy) in enemy_query.iter() {
+            if enemy.path_progress > 100.0 {
+                commands.entity(entity).remove::<Slowed>();
+            }
+        }
+    }
+
+    /// Enemies walking and setting off traps, a tenth of a second per update
+    fn trap_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                0.1,
+            )))
+            .init_resource::<GameState>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>();
+        app
+    }
+
+    #[test]
+    fn test_mine_does_full_damage_across_its_blast() {
+        let mut app = trap_app();
+        app.add_systems(Update, trigger_traps);
+        app.world_mut().spawn((
+            Trap {
+                kind: TrapKind::Mine,
+                uses_left: TrapKind::Mine.uses(),
+                path_progress: 100.0,
+            },
+            Transform::default(),
+        ));
+        // One stepping on the mine, one near the edge of the blast, one outside it
+        let enemies = [0.0, 45.0, 60.0].map(|x| {
+            app.world_mut()
+                .spawn((
+                    Enemy {
+                        health: 200.0,
+                        max_health: 200.0,
+                        speed: 50.0,
+                        path_progress: 101.0,
+                        last_progress: 99.0,
+                    },
+                    Transform::from_xyz(x, 0.0, 0.0),
+                ))
+                .id()
+        });
+
+        app.update();
+
+        let health = enemies.map(|enemy| app.world().get::<Enemy>(enemy).unwrap().health);
+        assert_eq!(health, [120.0, 120.0, 200.0]);
+    }
+
+    #[test]
+    fn test_trap_fires_once_when_slow_wears_off_mid_frame() {
+        let mut app = trap_app();
+        app.add_systems(
+            Update,
+            (move_enemies, slow_wears_off, trigger_traps).chain(),
+        );
+        let trap = app
+            .world_mut()
+            .spawn((
+                Trap {
+                    kind: TrapKind::Spikes,
+                    uses_left: TrapKind::Spikes.uses(),
+                    path_progress: 100.0,
+                },
+                Transform::default(),
+            ))
+            .id();
+        // Lands on the trap on the first frame at half speed, then walks on
+        app.world_mut().spawn((
+            Enemy {
+                health: 100.0,
+                max_health: 100.0,
+                speed: 50.0,
+                path_progress: 97.5,
+                last_progress: 97.5,
+            },
+            Slowed {
+                factor: 0.5,
+                timer: Timer::from_seconds(10.0, TimerMode::Once),
+            },
+            Transform::default(),
+        ));
+
+        for _ in 0..4 {
+            app.update();
+        }
+
+        let trap = app.world().get::<Trap>(trap).unwrap();
+        assert_eq!(trap.uses_left, TrapKind::Spikes.uses() - 1);
+    }
+}
@@ -1,9 +1,13 @@
+use super::relocation::move_button_label;
+use super::selection::sell_value;
+use super::synergy::active_synergies;
 use crate::components::*;
 use crate::resources::*;
 use bevy::prelude::*;
 
 pub fn update_ui(
     game_state: Res<GameState>,
+    wave_timer: Res<WaveTimer>,
     mut health_text_query: Query<&mut Text, (With<HealthText>, Without<ScoreText>)>,
     mut score_text_query: Query<&mut Text, (With<ScoreText>, Without<HealthText>)>,
 ) {
@@ -12,6 +16,284 @@ pub fn update_ui(
     }
 
     if let Ok(mut text) = score_text_query.get_single_mut() {
-        **text = format!("Score: {} | Money: {}", game_state.score, game_state.money);
+        **text = format!(
+            "Score: {} | Money: {} | Wave: {}",
+            game_state.score, game_state.money, wave_timer.wave
+        );
+    }
+}
+
+pub fn update_status_text(
+    mut status: ResMut<StatusMessage>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<StatusText>>,
+    time: Res<Time>,
+) {
+    status.timer.tick(time.delta());
+
+    if let Ok((mut text, mut visibility)) = text_query.get_single_mut() {
+        if status.is_visible() {
+            if **text != status.text {
+                **text = status.text.clone();
+            }
+            *visibility = Visibility::Inherited;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)] // UI queries need many filters
+pub fn update_tower_panel(
+    selected: Res<SelectedTower>,
+    wave_timer: Res<WaveTimer>,
+    config: Res<GameConfig>,
+    tower_query: Query<(
+        &Tower,
+        &EffectiveStats,
+        &TowerInvestment,
+        &CombatRecord,
+        &Rank,
+        Option<&Aura>,
+        Option<&Ability>,
+        Option<&RelocationCooldown>,
+    )>,
+    mut panel_query: Query<&mut Node, With<TowerPanel>>,
+    mut attack_button_query: Query<
+        &mut Node,
+        (
+            Or<(With<TargetingButton>, With<LeadButton>)>,
+            Without<TowerPanel>,
+            Without<AbilityButton>,
+        ),
+    >,
+    mut ability_button_query: Query<
+        (&mut Node, &Children),
+        (With<AbilityButton>, Without<TowerPanel>),
+    >,
+    placed_query: Query<(Entity, &Tower, &Transform)>,
+    info_query: Query<Entity, With<TowerInfoText>>,
+    targeting_button_query: Query<&Children, With<TargetingButton>>,
+    lead_button_query: Query<&Children, With<LeadButton>>,
+    upgrade_button_query: Query<&Children, With<UpgradeButton>>,
+    move_button_query: Query<&Children, With<MoveButton>>,
+    relocating: Res<Relocating>,
+    mut text_query: Query<&mut Text>,
+) {
+    let Ok(mut panel) = panel_query.get_single_mut() else {
+        return;
+    };
+
+    let Some((entity, (tower, stats, investment, record, rank, aura, ability, relocation))) =
+        selected
+            .0
+            .and_then(|entity| Some((entity, tower_query.get(entity).ok()?)))
+    else {
+        panel.display = Display::None;
+        return;
+    };
+
+    panel.display = Display::Flex;
+
+    // Targeting settings only mean something for towers that shoot
+    for mut node in attack_button_query.iter_mut() {
+        node.display = if tower.kind.attacks() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+
+    if let Ok((mut node, children)) = ability_button_query.get_single_mut() {
+        match ability {
+            Some(ability) => {
+                node.display = Display::Flex;
+                let state = if ability.is_active() {
+                    "active".to_string()
+                } else if ability.is_ready() {
+                    "ready".to_string()
+                } else {
+                    format!("{:.0}s", ability.cooldown.ceil())
+                };
+                for &child in children.iter() {
+                    set_text(
+                        &mut text_query,
+                        child,
+                        format!("{} ({})", ability.kind.name(), state),
+                    );
+                }
+            }
+            None => node.display = Display::None,
+        }
+    }
+
+    let details = match aura {
+        Some(aura) => format!(
+            "Aura radius: {:.0}\nDamage: +{:.0}%\nRange: +{:.0}%\nFire rate: +{:.0}%{}",
+            aura.radius,
+            aura.modifier.damage * 100.0,
+            aura.modifier.range * 100.0,
+            aura.modifier.fire_rate * 100.0,
+            if aura.modifier.reveals_stealth {
+                "\nReveals stealth"
+            } else {
+                ""
+            }
+        ),
+        None if tower.kind.attacks() => format!(
+            "Damage: {:.0}{}\nRange: {:.0}\nFire rate: {:.1}/s{}{}\nRank: {}\n\nDamage dealt: {:.0}\nKills: {}\nShots: {} ({:.0}% hit)\nDPS: {:.1}",
+            stats.damage,
+            if stats.damage_spread > 0.0 {
+                format!(" (+/-{:.0}%)", stats.damage_spread * 100.0)
+            } else {
+                String::new()
+            },
+            stats.range,
+            stats.fire_rate,
+            if stats.crit_chance > 0.0 {
+                format!(
+                    "\nCrit: {:.0}% for x{:.1}",
+                    stats.crit_chance * 100.0,
+                    stats.crit_multiplier
+                )
+            } else {
+                String::new()
+            },
+            if stats.slowed_damage > 0.0 {
+                format!("\nVs slowed: +{:.0}%", stats.slowed_damage * 100.0)
+            } else {
+                String::new()
+            },
+            rank.title(),
+            record.damage_dealt,
+            record.kills,
+            record.shots_fired,
+            record.hit_rate() * 100.0,
+            record.dps()
+        ),
+        None => format!("Income: ${} per wave", tower.kind.income(tower.level)),
+    };
+
+    let towers: Vec<_> = placed_query
+        .iter()
+        .map(|(entity, tower, transform)| (entity, tower.kind, transform.translation.truncate()))
+        .collect();
+    let synergies = towers
+        .iter()
+        .find(|(other, _, _)| *other == entity)
+        .map(|(_, kind, position)| active_synergies(entity, *kind, *position, &towers))
+        .unwrap_or_default();
+    let synergy_line = if synergies.is_empty() {
+        String::new()
+    } else {
+        let names: Vec<_> = synergies.iter().map(|synergy| synergy.name).collect();
+        format!("\nSynergies: {}", names.join(", "))
+    };
+
+    if let Ok(info) = info_query.get_single() {
+        set_text(
+            &mut text_query,
+            info,
+            format!(
+                "{} (level {})\n{}{}\nSell value: {}",
+                tower.kind.name(),
+                tower.level,
+                details,
+                synergy_line,
+                sell_value(investment, wave_timer.wave, config.sell_refund_ratio)
+            ),
+        );
+    }
+
+    set_button_label(
+        &targeting_button_query,
+        &mut text_query,
+        format!("Target: {}", tower.targeting.label()),
+    );
+    set_button_label(
+        &lead_button_query,
+        &mut text_query,
+        format!("Lead: {}", if tower.lead_targeting { "On" } else { "Off" }),
+    );
+    set_button_label(
+        &move_button_query,
+        &mut text_query,
+        move_button_label(
+            config.relocation_fee,
+            relocation.map(|cooldown| cooldown.0.remaining_secs()),
+            relocating.0 == Some(entity),
+        ),
+    );
+    set_button_label(
+        &upgrade_button_query,
+        &mut text_query,
+        if tower.level >= Tower::MAX_LEVEL {
+            "Max level".to_string()
+        } else {
+            format!("Upgrade (${})", tower.kind.upgrade_cost(tower.level))
+        },
+    );
+}
+
+/// Grey out cards the player can't afford and highlight the current build choice
+pub fn update_build_menu(
+    game_state: Res<GameState>,
+    build_selection: Res<BuildSelection>,
+    mut card_query: Query<(
+        &BuildCard,
+        &Children,
+        &mut BackgroundColor,
+        &mut BorderColor,
+    )>,
+    mut text_color_query: Query<&mut TextColor>,
+) {
+    if !game_state.is_changed() && !build_selection.is_changed() {
+        return;
+    }
+
+    for (card, children, mut background, mut border) in card_query.iter_mut() {
+        let affordable = game_state.money >= card.0.cost();
+        let selected = build_selection.0 == Some(card.0);
+
+        background.0 = if affordable {
+            Color::srgba(0.15, 0.15, 0.2, 0.9)
+        } else {
+            Color::srgba(0.08, 0.08, 0.08, 0.9)
+        };
+        border.0 = if selected {
+            Color::srgb(1.0, 0.85, 0.2)
+        } else {
+            Color::srgb(0.3, 0.3, 0.35)
+        };
+
+        for &child in children.iter() {
+            if let Ok(mut color) = text_color_query.get_mut(child) {
+                color.0 = if affordable {
+                    Color::WHITE
+                } else {
+                    Color::srgb(0.45, 0.45, 0.45)
+                };
+            }
+        }
+    }
+}
+
+fn set_text(text_query: &mut Query<&mut Text>, entity: Entity, value: String) {
+    if let Ok(mut text) = text_query.get_mut(entity) {
+        if **text != value {
+            **text = value;
+        }
+    }
+}
+
+/// Buttons carry their label as a `Text` child
+fn set_button_label<F: bevy::ecs::query::QueryFilter>(
+    button_query: &Query<&Children, F>,
+    text_query: &mut Query<&mut Text>,
+    label: String,
+) {
+    for children in button_query.iter() {
+        for &child in children.iter() {
+            set_text(text_query, child, label.clone());
+        }
     }
 }